@@ -2,4 +2,5 @@
 cp /src/Cargo.toml /app/Cargo.toml
 cp /src/Cargo.lock /app/Cargo.lock
 rsync -r /src/src/* /app/src/
-cargo tarpaulin --out html --output-dir /src/coverage
\ No newline at end of file
+rsync -r /src/subversive_derive /app/
+cargo tarpaulin --out html --output-dir /src/coverage
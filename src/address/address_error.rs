@@ -0,0 +1,53 @@
+use std::fmt::Display;
+
+/// Why an address string was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    /// The string does not start with the address prefix
+    BadPrefix,
+    /// The string is not valid Base58
+    BadEncoding,
+    /// The version byte is not a known address version
+    BadVersion(u8),
+    /// The decoded bytes have the wrong length for the version
+    BadLength(usize),
+    /// The checksum does not match, usually a typo
+    BadChecksum,
+    /// A short id must be non-empty lowercase hex no longer than a fingerprint
+    BadShortId(String),
+    /// No known address has a fingerprint starting with this short id
+    UnknownShortId(String),
+    /// Several known addresses match the short id, listed by fingerprint
+    AmbiguousShortId(String, Vec<String>),
+    /// A multisig threshold must be between 1 and the number of members
+    BadThreshold(u64, usize),
+    /// A multisig member is listed twice or is itself a multisig address
+    BadMember(String),
+}
+
+impl Display for AddressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadPrefix => write!(f, "Address must start with \"{}\"", super::ADDRESS_PREFIX),
+            Self::BadEncoding => write!(f, "Address is not valid Base58"),
+            Self::BadVersion(version) => write!(f, "Unknown address version {version}"),
+            Self::BadLength(len) => write!(f, "Address has the wrong length ({len} bytes)"),
+            Self::BadChecksum => write!(f, "Address checksum does not match"),
+            Self::BadShortId(id) => write!(f, "Invalid short id {id:?}"),
+            Self::UnknownShortId(id) => write!(f, "No address matches short id {id:?}"),
+            Self::AmbiguousShortId(id, matches) => write!(
+                f,
+                "Short id {id:?} matches {} addresses: {}",
+                matches.len(),
+                matches.join(", ")
+            ),
+            Self::BadThreshold(threshold, members) => write!(
+                f,
+                "Threshold {threshold} is not between 1 and {members} members"
+            ),
+            Self::BadMember(member) => write!(f, "Invalid multisig member {member}"),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
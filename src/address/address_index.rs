@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use base_xx::SerialiseError;
+
+use crate::address::fingerprint::{DEFAULT_SHORT_ID_LEN, FINGERPRINT_LEN};
+use crate::address::public_address::PublicAddress;
+use crate::address::{AddressError, Fingerprint};
+
+/// Known addresses, looked up by short id
+///
+/// Keyed by the hex fingerprint, so every address whose fingerprint starts with
+/// a short id is one contiguous range of the map.
+#[derive(Debug, Default)]
+pub struct AddressIndex {
+    addresses: BTreeMap<String, Rc<PublicAddress>>,
+}
+
+impl AddressIndex {
+    /// Create an empty index
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            addresses: BTreeMap::new(),
+        }
+    }
+
+    /// Number of known addresses
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Whether no addresses are known
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Add an address, returning its fingerprint
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be fingerprinted
+    pub fn insert(&mut self, address: Rc<PublicAddress>) -> Result<Fingerprint, SerialiseError> {
+        let fingerprint = address.fingerprint()?;
+        self.addresses.insert(fingerprint.to_string(), address);
+        Ok(fingerprint)
+    }
+
+    /// Every known address whose fingerprint starts with `short_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `short_id` is empty, too long or not hex
+    pub fn matches(&self, short_id: &str) -> Result<Vec<Rc<PublicAddress>>, AddressError> {
+        let prefix = short_id.to_ascii_lowercase();
+        if prefix.is_empty()
+            || prefix.len() > FINGERPRINT_LEN * 2
+            || !prefix.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Err(AddressError::BadShortId(short_id.to_string()));
+        }
+        Ok(self
+            .addresses
+            .range(prefix.clone()..)
+            .take_while(|(fingerprint, _)| fingerprint.starts_with(&prefix))
+            .map(|(_, address)| Rc::clone(address))
+            .collect())
+    }
+
+    /// The one known address whose fingerprint starts with `short_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the short id is invalid, matches nothing, or matches
+    /// several addresses
+    pub fn resolve(&self, short_id: &str) -> Result<Rc<PublicAddress>, AddressError> {
+        let mut matches = self.matches(short_id)?;
+        match matches.len() {
+            0 => Err(AddressError::UnknownShortId(short_id.to_string())),
+            1 => Ok(matches.remove(0)),
+            _ => Err(AddressError::AmbiguousShortId(
+                short_id.to_string(),
+                matches
+                    .iter()
+                    .filter_map(|address| address.fingerprint().ok())
+                    .map(|fingerprint| fingerprint.to_string())
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Shortest short id, at least `DEFAULT_SHORT_ID_LEN` long, that resolves
+    /// to `address` alone
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be fingerprinted
+    pub fn short_id(&self, address: &PublicAddress) -> Result<String, SerialiseError> {
+        let fingerprint = address.fingerprint()?.to_string();
+        let unique_len = (DEFAULT_SHORT_ID_LEN..fingerprint.len())
+            .find(|&len| {
+                self.matches(&fingerprint[..len])
+                    .is_ok_and(|matches| matches.iter().all(|other| **other == *address))
+            })
+            .unwrap_or(fingerprint.len());
+        Ok(fingerprint[..unique_len].to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::public_address::CHECKSUM_LEN;
+    use crate::address::ADDRESS_PREFIX;
+    use crate::test_util::hex;
+    use base_xx::Base58;
+    use simple_sign::Ed25519Signer;
+
+    fn random_address() -> Rc<PublicAddress> {
+        Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        )
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let address = random_address();
+        let fingerprint = address
+            .fingerprint()
+            .unwrap_or_else(|e| panic!("fingerprint: {e}"));
+        assert_eq!(fingerprint.to_string().len(), FINGERPRINT_LEN * 2);
+        assert_eq!(fingerprint.short_id(DEFAULT_SHORT_ID_LEN).len(), 8);
+        assert_eq!(fingerprint.short_id(100), fingerprint.to_string());
+        assert_eq!(address.fingerprint().ok(), Some(fingerprint));
+        assert_ne!(random_address().fingerprint().ok(), Some(fingerprint));
+
+        // the short id must not just repeat the checksum at the end of the address
+        let text = address.to_string();
+        let bytes = Base58::base58_to_bytes(&text[ADDRESS_PREFIX.len()..])
+            .unwrap_or_else(|e| panic!("decode: {e}"));
+        let checksum = hex(&bytes[bytes.len() - CHECKSUM_LEN..]);
+        assert_ne!(fingerprint.short_id(DEFAULT_SHORT_ID_LEN), checksum);
+    }
+
+    #[test]
+    fn test_resolve_short_id() {
+        let mut index = AddressIndex::new();
+        let address = random_address();
+        let fingerprint = index
+            .insert(Rc::clone(&address))
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+        index
+            .insert(random_address())
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+
+        let short_id = fingerprint.short_id(DEFAULT_SHORT_ID_LEN);
+        assert_eq!(index.resolve(&short_id).ok(), Some(Rc::clone(&address)));
+        assert_eq!(
+            index.resolve(&short_id.to_uppercase()).ok(),
+            Some(Rc::clone(&address))
+        );
+        assert_eq!(index.short_id(&address).ok(), Some(short_id));
+
+        assert_eq!(
+            index.resolve("xyz"),
+            Err(AddressError::BadShortId("xyz".to_string()))
+        );
+        assert_eq!(
+            index.resolve(""),
+            Err(AddressError::BadShortId(String::new()))
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_short_id() {
+        let mut index = AddressIndex::new();
+        let mut fingerprints = vec![];
+        // 17 fingerprints guarantee two share a first hex character
+        for _ in 0..17 {
+            fingerprints.push(
+                index
+                    .insert(random_address())
+                    .unwrap_or_else(|e| panic!("insert: {e}"))
+                    .to_string(),
+            );
+        }
+        fingerprints.sort();
+        let shared = fingerprints
+            .windows(2)
+            .find(|pair| pair[0][..1] == pair[1][..1])
+            .map_or_else(|| unreachable!(), |pair| pair[0][..1].to_string());
+
+        match index.resolve(&shared) {
+            Err(AddressError::AmbiguousShortId(id, matches)) => {
+                assert_eq!(id, shared);
+                assert!(matches.len() >= 2);
+                assert!(matches.iter().all(|m| m.starts_with(&shared)));
+            }
+            other => panic!("expected ambiguity, got {other:?}"),
+        }
+
+        let unused = [
+            "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "a", "b", "c", "d", "e", "f",
+        ]
+        .into_iter()
+        .find(|c| !fingerprints.iter().any(|f| f.starts_with(c)));
+        if let Some(unused) = unused {
+            assert_eq!(
+                index.resolve(unused),
+                Err(AddressError::UnknownShortId(unused.to_string()))
+            );
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt::Display;
+use std::sync::Arc;
+
+use base_xx::{ByteVec, SerialiseError};
+use slahasher::{Hash, HashAlgorithm};
+
+use crate::address::public_address::PublicAddress;
+
+/// Number of bytes in a fingerprint
+pub const FINGERPRINT_LEN: usize = 16;
+
+/// Number of hex characters in a default short id
+pub const DEFAULT_SHORT_ID_LEN: usize = 8;
+
+/// Prefix of the hashed bytes, so a fingerprint never repeats the checksum
+/// already shown in the address text
+const FINGERPRINT_PREFIX: &[u8] = b"fingerprint";
+
+/// Fixed-size digest of an encoded `PublicAddress`
+///
+/// The first `FINGERPRINT_LEN` bytes of the KECCAK256 hash of `"fingerprint"`
+/// followed by `[version][public_key]`. Displays as lowercase hex, and a prefix of that hex
+/// is the short id used in threads and logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint([u8; FINGERPRINT_LEN]);
+
+impl Fingerprint {
+    /// Get the fingerprint bytes
+    #[must_use]
+    pub const fn get_bytes(&self) -> &[u8; FINGERPRINT_LEN] {
+        &self.0
+    }
+
+    /// First `len` hex characters, capped at the full fingerprint
+    #[must_use]
+    pub fn short_id(&self, len: usize) -> String {
+        let mut id = self.to_string();
+        id.truncate(len);
+        id
+    }
+}
+
+impl TryFrom<&PublicAddress> for Fingerprint {
+    type Error = SerialiseError;
+
+    fn try_from(value: &PublicAddress) -> Result<Self, Self::Error> {
+        let bytes = [FINGERPRINT_PREFIX, ByteVec::try_from(value)?.get_bytes()].concat();
+        let hash = Hash::try_hash(
+            Arc::new(ByteVec::new(bytes.into())),
+            HashAlgorithm::KECCAK256,
+        )?;
+        let mut bytes = [0u8; FINGERPRINT_LEN];
+        bytes.copy_from_slice(&hash.get_bytes().get_bytes()[..FINGERPRINT_LEN]);
+        Ok(Self(bytes))
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use base_xx::SerialiseError;
+use simple_sign::SigningAlgorithm;
+
+/// Key algorithm of an address, selected by its version byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyAlgorithm {
+    /// Ed25519 keys, address version 1
+    Ed25519,
+    /// Secp256k1 ECDSA keys in compressed SEC1 form, address version 2
+    Secp256k1,
+    /// m-of-n shared account, address version 3
+    ///
+    /// The key is the KECCAK256 hash of the `MultisigPolicy`; it cannot sign.
+    Multisig,
+}
+
+impl KeyAlgorithm {
+    /// Address version byte for this algorithm
+    #[must_use]
+    pub const fn version(self) -> u8 {
+        match self {
+            Self::Ed25519 => 1,
+            Self::Secp256k1 => 2,
+            Self::Multisig => 3,
+        }
+    }
+
+    /// Length of a public key in bytes
+    #[must_use]
+    pub const fn public_key_len(self) -> usize {
+        match self {
+            Self::Ed25519 | Self::Multisig => 32,
+            Self::Secp256k1 => 33,
+        }
+    }
+
+    /// Signature algorithm produced by keys of this kind, `None` for multisig
+    #[must_use]
+    pub const fn signing_algorithm(self) -> Option<SigningAlgorithm> {
+        match self {
+            Self::Ed25519 => Some(SigningAlgorithm::ED25519),
+            Self::Secp256k1 => Some(SigningAlgorithm::ECDSA),
+            Self::Multisig => None,
+        }
+    }
+}
+
+impl TryFrom<u8> for KeyAlgorithm {
+    type Error = SerialiseError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Ed25519),
+            2 => Ok(Self::Secp256k1),
+            3 => Ok(Self::Multisig),
+            _ => Err(SerialiseError::new(format!(
+                "Unknown PublicAddress version {value}"
+            ))),
+        }
+    }
+}
+
+impl From<KeyAlgorithm> for u8 {
+    fn from(value: KeyAlgorithm) -> Self {
+        value.version()
+    }
+}
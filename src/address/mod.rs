@@ -1 +1,25 @@
+/// Address parsing errors
+pub mod address_error;
+
+/// Short id lookup of known addresses
+pub mod address_index;
+
+/// Fixed-size address fingerprints
+pub mod fingerprint;
+
+/// Key algorithms selected by the address version
+pub mod key_algorithm;
+
+/// m-of-n shared account policies
+pub mod multisig_policy;
+
 pub mod public_address;
+
+pub use address_error::AddressError;
+pub use address_index::AddressIndex;
+pub use fingerprint::Fingerprint;
+pub use key_algorithm::KeyAlgorithm;
+pub use multisig_policy::MultisigPolicy;
+
+/// Prefix of every address string
+pub const ADDRESS_PREFIX: &str = "sv";
@@ -0,0 +1,99 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use base_xx::{ByteVec, SerialiseError};
+use slahasher::{Hash, HashAlgorithm};
+
+use crate::address::public_address::PublicAddress;
+use crate::address::{AddressError, KeyAlgorithm};
+use crate::serialise::{RLEFieldIter, RleDecode, RleDecodeField, RleEncode};
+
+/// Members and threshold of an m-of-n shared account
+///
+/// Members are kept sorted so the same set always yields the same address.
+/// Encodes as RLE fields `[threshold][members]`; decoding enforces the same
+/// rules as `MultisigPolicy::new` and also requires the members in order.
+///
+/// A policy's address cannot hold funds yet: the mempool, blocks and ledger
+/// only take single-signer `SignedTransaction`s, so a `MultisigTransaction`
+/// can be built and verified but not applied. Until it can, the ledger and
+/// mempool refuse payments to multisig addresses rather than lose them.
+#[derive(Debug, PartialEq, Eq, RleEncode)]
+pub struct MultisigPolicy {
+    threshold: u64,
+    members: Vec<Rc<PublicAddress>>,
+}
+
+impl MultisigPolicy {
+    /// Create a policy needing `threshold` of `members` to sign
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the threshold is 0 or above the member count, or a
+    /// member is repeated or is itself a multisig address
+    pub fn new(threshold: u64, mut members: Vec<Rc<PublicAddress>>) -> Result<Self, AddressError> {
+        members.sort();
+        if let Some(pair) = members.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(AddressError::BadMember(pair[0].to_string()));
+        }
+        if let Some(member) = members
+            .iter()
+            .find(|member| member.get_algorithm() == KeyAlgorithm::Multisig)
+        {
+            return Err(AddressError::BadMember(member.to_string()));
+        }
+        if threshold == 0 || usize::try_from(threshold).map_or(true, |t| t > members.len()) {
+            return Err(AddressError::BadThreshold(threshold, members.len()));
+        }
+        Ok(Self { threshold, members })
+    }
+
+    /// Get number of members that must sign
+    #[must_use]
+    pub const fn get_threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    /// Get members, sorted
+    #[must_use]
+    pub fn get_members(&self) -> &[Rc<PublicAddress>] {
+        &self.members
+    }
+
+    /// Address of the shared account, the KECCAK256 hash of the encoded policy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the policy cannot be encoded or hashed
+    pub fn address(&self) -> Result<PublicAddress, SerialiseError> {
+        let hash = Hash::try_hash(Arc::new(self.to_byte_vec()?), HashAlgorithm::KECCAK256)?;
+        Ok(PublicAddress::new_with_algorithm(
+            KeyAlgorithm::Multisig,
+            ByteVec::new(hash.get_bytes().get_bytes().to_vec().into()),
+        ))
+    }
+}
+
+impl RleDecode for MultisigPolicy {
+    fn from_fields(fields: &mut RLEFieldIter<'_>) -> Result<Self, SerialiseError> {
+        let threshold = u64::decode_field(fields, "threshold")?;
+        let members = Vec::<Rc<PublicAddress>>::decode_field(fields, "members")?;
+        let policy = Self::new(threshold, members.clone())
+            .map_err(|e| SerialiseError::new(format!("Invalid multisig policy: {e}")))?;
+        if policy.members != members {
+            return Err(SerialiseError::new(
+                "Multisig members are not in order".to_string(),
+            ));
+        }
+        Ok(policy)
+    }
+}
+
+impl RleDecodeField for MultisigPolicy {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let mut nested = fields.next_rle(name)?.iter();
+        let value = Self::from_fields(&mut nested)?;
+        nested.finish(name)?;
+        Ok(value)
+    }
+}
@@ -1,25 +1,37 @@
 //! Public address type and byte encoding/decoding.
 
-use base_xx::{byte_vec::Encodable, ByteVec};
+use base_xx::{byte_vec::Encodable, Base58, ByteVec};
+use std::fmt::Display;
+use std::str::FromStr;
 use std::sync::Arc;
 
-use simple_sign::Ed25519Signer;
-use slahasher::Hashable;
+use k256::ecdsa::signature::Verifier;
+use simple_sign::{Ed25519Signer, Secp256k1Signer, Signature};
+use slahasher::{Hash, HashAlgorithm, Hashable};
+
+use crate::address::{AddressError, Fingerprint, KeyAlgorithm, ADDRESS_PREFIX};
+
+/// Number of checksum bytes appended to an address string
+pub const CHECKSUM_LEN: usize = 4;
 
 /// A public address.
 ///
-/// Encodes to bytes as: `[version][public_key_bytes...]`.
+/// Encodes to bytes as: `[version][public_key_bytes...]`, where the version
+/// selects the `KeyAlgorithm` and so the expected key length.
+///
+/// The text form, via `Display` and `FromStr`, is `sv` followed by the Base58
+/// of those bytes with a `CHECKSUM_LEN` byte KECCAK256 checksum appended.
 #[derive(Debug, PartialEq, PartialOrd, Ord, Eq)]
 pub struct PublicAddress {
     public_key: ByteVec,
-    version: u8,
+    algorithm: KeyAlgorithm,
 }
 
 impl Default for PublicAddress {
     fn default() -> Self {
         Self {
             public_key: ByteVec::new(vec![].into()),
-            version: 1,
+            algorithm: KeyAlgorithm::Ed25519,
         }
     }
 }
@@ -28,9 +40,15 @@ impl PublicAddress {
     #[must_use]
     /// Creates a new `PublicAddress` with the default version.
     pub const fn new(public_key: ByteVec) -> Self {
+        Self::new_with_algorithm(KeyAlgorithm::Ed25519, public_key)
+    }
+
+    #[must_use]
+    /// Creates a new `PublicAddress` for a key of the given algorithm.
+    pub const fn new_with_algorithm(algorithm: KeyAlgorithm, public_key: ByteVec) -> Self {
         Self {
             public_key,
-            version: 1,
+            algorithm,
         }
     }
 
@@ -43,7 +61,58 @@ impl PublicAddress {
     #[must_use]
     /// Returns the address version byte.
     pub const fn get_version(&self) -> u8 {
-        self.version
+        self.algorithm.version()
+    }
+
+    #[must_use]
+    /// Returns the key algorithm selected by the version.
+    pub const fn get_algorithm(&self) -> KeyAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the fingerprint of the encoded address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be encoded or hashed
+    pub fn fingerprint(&self) -> Result<Fingerprint, base_xx::SerialiseError> {
+        Fingerprint::try_from(self)
+    }
+
+    #[must_use]
+    /// Checks that `signature` is this address's key signing `hash`.
+    pub fn verify(&self, hash: &Hash, signature: &Signature) -> bool {
+        if Some(signature.get_algorithm()) != self.algorithm.signing_algorithm() {
+            return false;
+        }
+        let message = hash.get_bytes().get_bytes();
+        let key = self.public_key.get_bytes();
+        let signature = signature.get_signature();
+
+        match self.algorithm {
+            KeyAlgorithm::Ed25519 => {
+                let Ok(key) = <[u8; 32]>::try_from(key) else {
+                    return false;
+                };
+                let Ok(signature) = ed25519_dalek::Signature::from_slice(signature.get_bytes())
+                else {
+                    return false;
+                };
+                ed25519_dalek::VerifyingKey::from_bytes(&key)
+                    .and_then(|key| key.verify_strict(message, &signature))
+                    .is_ok()
+            }
+            KeyAlgorithm::Secp256k1 => {
+                let Ok(signature) = k256::ecdsa::Signature::from_slice(signature.get_bytes())
+                else {
+                    return false;
+                };
+                k256::ecdsa::VerifyingKey::from_sec1_bytes(key)
+                    .and_then(|key| key.verify(message, &signature))
+                    .is_ok()
+            }
+            KeyAlgorithm::Multisig => false,
+        }
     }
 }
 
@@ -51,7 +120,7 @@ impl TryFrom<&PublicAddress> for ByteVec {
     type Error = base_xx::SerialiseError;
     fn try_from(value: &PublicAddress) -> Result<Self, Self::Error> {
         let mut bytes = Vec::with_capacity(1 + value.public_key.get_bytes().len());
-        bytes.push(value.version);
+        bytes.push(value.get_version());
         bytes.extend_from_slice(value.public_key.get_bytes());
         Ok(Self::new(bytes.into()))
     }
@@ -66,21 +135,29 @@ impl base_xx::byte_vec::TryIntoByteVec for PublicAddress {
 impl TryFrom<ByteVec> for PublicAddress {
     type Error = base_xx::SerialiseError;
     fn try_from(value: ByteVec) -> Result<Self, Self::Error> {
-        let bytes = value.get_bytes();
+        Self::try_from(value.get_bytes())
+    }
+}
+
+impl TryFrom<&[u8]> for PublicAddress {
+    type Error = base_xx::SerialiseError;
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
         if bytes.is_empty() {
             return Err(base_xx::SerialiseError::new(
                 "PublicAddress requires at least 1 byte for version".to_string(),
             ));
         }
 
-        let version = bytes[0];
-        if version != 1 {
-            return Err(base_xx::SerialiseError::new(
-                "PublicAddress version must be 1".to_string(),
-            ));
+        let algorithm = KeyAlgorithm::try_from(bytes[0])?;
+        let key_len = algorithm.public_key_len();
+        if bytes.len() - 1 != key_len {
+            return Err(base_xx::SerialiseError::new(format!(
+                "PublicAddress version {} requires a {key_len} byte key",
+                algorithm.version()
+            )));
         }
         let public_key = ByteVec::new(bytes[1..].to_vec().into());
-        Ok(Self::new(public_key))
+        Ok(Self::new_with_algorithm(algorithm, public_key))
     }
 }
 
@@ -93,6 +170,77 @@ impl TryFrom<&Ed25519Signer> for PublicAddress {
     }
 }
 
+impl TryFrom<&Secp256k1Signer> for PublicAddress {
+    type Error = base_xx::SerialiseError;
+    fn try_from(value: &Secp256k1Signer) -> Result<Self, Self::Error> {
+        let public_key = value.get_verifying_key().to_encoded_point(true);
+        let public_key = ByteVec::new(public_key.as_bytes().to_vec().into());
+        Ok(Self::new_with_algorithm(
+            KeyAlgorithm::Secp256k1,
+            public_key,
+        ))
+    }
+}
+
+impl PublicAddress {
+    /// First `CHECKSUM_LEN` bytes of the KECCAK256 hash of `[version][public_key]`
+    fn checksum(bytes: &[u8]) -> Result<[u8; CHECKSUM_LEN], base_xx::SerialiseError> {
+        let hash = Hash::try_hash(
+            Arc::new(ByteVec::new(bytes.to_vec().into())),
+            HashAlgorithm::KECCAK256,
+        )?;
+        let mut checksum = [0u8; CHECKSUM_LEN];
+        checksum.copy_from_slice(&hash.get_bytes().get_bytes()[..CHECKSUM_LEN]);
+        Ok(checksum)
+    }
+}
+
+impl Display for PublicAddress {
+    /// Renders `sv` followed by Base58 of `[version][public_key][checksum]`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut bytes = ByteVec::try_from(self)
+            .map_err(|_| std::fmt::Error)?
+            .get_bytes()
+            .to_vec();
+        let checksum = Self::checksum(&bytes).map_err(|_| std::fmt::Error)?;
+        bytes.extend_from_slice(&checksum);
+        write!(f, "{ADDRESS_PREFIX}{}", Base58::to_base58(&bytes))
+    }
+}
+
+impl FromStr for PublicAddress {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = s
+            .trim()
+            .strip_prefix(ADDRESS_PREFIX)
+            .ok_or(AddressError::BadPrefix)?;
+        if encoded.is_empty() {
+            return Err(AddressError::BadLength(0));
+        }
+        let bytes = Base58::base58_to_bytes(encoded).map_err(|_| AddressError::BadEncoding)?;
+        if bytes.len() <= CHECKSUM_LEN {
+            return Err(AddressError::BadLength(bytes.len()));
+        }
+
+        // checksum before version, so a typo anywhere reports a bad checksum
+        let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        let expected = Self::checksum(body).map_err(|_| AddressError::BadChecksum)?;
+        if checksum != expected {
+            return Err(AddressError::BadChecksum);
+        }
+
+        let algorithm =
+            KeyAlgorithm::try_from(bytes[0]).map_err(|_| AddressError::BadVersion(bytes[0]))?;
+        if bytes.len() != 1 + algorithm.public_key_len() + CHECKSUM_LEN {
+            return Err(AddressError::BadLength(bytes.len()));
+        }
+
+        Self::try_from(body).map_err(|_| AddressError::BadLength(bytes.len()))
+    }
+}
+
 impl Hashable for PublicAddress {}
 impl Encodable for PublicAddress {}
 
@@ -125,4 +273,137 @@ mod tests {
             .unwrap_or_else(|e| unreachable!("Failed to sign hash {e}"));
         debug!("signature: {signature:?}");
     }
+
+    #[test]
+    fn test_public_address_rejects_wrong_key_size() {
+        let mut bytes = vec![1u8];
+        bytes.extend_from_slice(&[7; 31]);
+        assert!(PublicAddress::try_from(bytes.as_slice()).is_err());
+        bytes.push(7);
+        assert!(PublicAddress::try_from(bytes.as_slice()).is_ok());
+        bytes.push(7);
+        assert!(PublicAddress::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_address_string_roundtrip() {
+        let address = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let text = address.to_string();
+        debug!("address: {text}");
+        assert!(text.starts_with(ADDRESS_PREFIX));
+
+        let parsed = text
+            .parse::<PublicAddress>()
+            .unwrap_or_else(|e| panic!("parse {text}: {e}"));
+        assert_eq!(parsed, address);
+    }
+
+    #[test]
+    fn test_address_string_errors() {
+        let address = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let text = address.to_string();
+
+        assert_eq!(
+            text[ADDRESS_PREFIX.len()..].parse::<PublicAddress>(),
+            Err(AddressError::BadPrefix)
+        );
+        assert_eq!(
+            format!("{text}0").parse::<PublicAddress>(),
+            Err(AddressError::BadEncoding)
+        );
+
+        // swap one character for another that keeps the string valid Base58
+        let last = text.chars().last().unwrap_or_else(|| unreachable!());
+        let typo = if last == 'a' { 'b' } else { 'a' };
+        let mistyped = format!("{}{typo}", &text[..text.len() - 1]);
+        assert_eq!(
+            mistyped.parse::<PublicAddress>(),
+            Err(AddressError::BadChecksum)
+        );
+
+        // a typo in the leading characters changes the version byte too
+        let first = text[ADDRESS_PREFIX.len()..]
+            .chars()
+            .next()
+            .unwrap_or_else(|| unreachable!());
+        let typo = if first == '2' { '3' } else { '2' };
+        let mistyped = format!(
+            "{ADDRESS_PREFIX}{typo}{}",
+            &text[ADDRESS_PREFIX.len() + 1..]
+        );
+        assert_eq!(
+            mistyped.parse::<PublicAddress>(),
+            Err(AddressError::BadChecksum)
+        );
+
+        let short = format!("{ADDRESS_PREFIX}{}", Base58::to_base58(&[1, 2, 3]));
+        assert_eq!(
+            short.parse::<PublicAddress>(),
+            Err(AddressError::BadLength(3))
+        );
+
+        let mut versioned = vec![9u8];
+        versioned.extend_from_slice(&[0; 32]);
+        let checksum = PublicAddress::checksum(&versioned).unwrap_or_else(|e| panic!("{e}"));
+        versioned.extend_from_slice(&checksum);
+        let versioned = format!("{ADDRESS_PREFIX}{}", Base58::to_base58(&versioned));
+        assert_eq!(
+            versioned.parse::<PublicAddress>(),
+            Err(AddressError::BadVersion(9))
+        );
+    }
+
+    fn sign(signer: Arc<impl Signer>, hash: &Arc<Hash>) -> Arc<Signature> {
+        signer
+            .sign(Arc::clone(hash))
+            .unwrap_or_else(|e| unreachable!("Failed to sign hash {e}"))
+    }
+
+    #[test]
+    fn test_verify_each_algorithm() {
+        let hash = Hash::try_hash(
+            Arc::new(ByteVec::new(b"message".to_vec().into())),
+            HashAlgorithm::KECCAK512,
+        )
+        .unwrap_or_else(|e| unreachable!("Failed to hash {e}"));
+
+        let ed25519 = Ed25519Signer::new_random();
+        let ed25519_address = PublicAddress::try_from(&ed25519).unwrap_or_else(|_| unreachable!());
+        let ed25519_signature = sign(Arc::new(ed25519), &hash);
+
+        let secp256k1 = Secp256k1Signer::new_random();
+        let secp256k1_address =
+            PublicAddress::try_from(&secp256k1).unwrap_or_else(|_| unreachable!());
+        let secp256k1_signature = sign(Arc::new(secp256k1), &hash);
+
+        assert_eq!(ed25519_address.get_version(), 1);
+        assert_eq!(secp256k1_address.get_version(), 2);
+        assert!(ed25519_address.verify(&hash, &ed25519_signature));
+        assert!(secp256k1_address.verify(&hash, &secp256k1_signature));
+        assert!(!ed25519_address.verify(&hash, &secp256k1_signature));
+        assert!(!secp256k1_address.verify(&hash, &ed25519_signature));
+
+        let other = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        assert!(!other.verify(&hash, &ed25519_signature));
+    }
+
+    #[test]
+    fn test_secp256k1_address_roundtrip() {
+        let address = PublicAddress::try_from(&Secp256k1Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+
+        let bytes = ByteVec::try_from(&address).unwrap_or_else(|e| panic!("encode: {e}"));
+        assert_eq!(bytes.get_bytes().len(), 34);
+        let decoded = PublicAddress::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded, address);
+
+        let parsed = address
+            .to_string()
+            .parse::<PublicAddress>()
+            .unwrap_or_else(|e| panic!("parse: {e}"));
+        assert_eq!(parsed, address);
+    }
 }
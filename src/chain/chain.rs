@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use slahasher::Hash;
+
+use crate::chain::ChainError;
+use crate::consensus::{Consensus, ConsensusError, Slot};
+use crate::game::{Block, BlockError};
+use crate::ledger::Ledger;
+use crate::transactions::SignedTransaction;
+
+/// Blocks accepted so far, from genesis at height 0 to the tip
+///
+/// A block is appended only if it builds on the tip, is timed after the tip
+/// in a later slot that has already started, and is a valid candidate for
+/// its slot. Blocks are indexed by height and by hash.
+///
+/// Only the tip can be extended. A block building on any earlier block is
+/// refused with [`ChainError::NotOnTip`]: side branches are not kept and the
+/// chain never reorganises, so callers holding competing chains choose between
+/// them with [`Consensus::fork_choice`].
+///
+/// [`Chain::check`] covers only timing and consensus rules. [`Chain::append`]
+/// then applies the block's transactions to the ledger, so the chain and ledger
+/// move together: a block refused by either changes neither.
+#[derive(Debug)]
+pub struct Chain {
+    consensus: Consensus,
+    blocks: Vec<Block>,
+    hashes: Vec<Arc<Hash>>,
+    heights: BTreeMap<Arc<Hash>, u64>,
+}
+
+impl Chain {
+    /// Start a chain from `genesis`, accepting blocks from `consensus`'s signers
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the genesis block cannot be hashed
+    pub fn new(genesis: Block, consensus: Consensus) -> Result<Self, ChainError> {
+        let hash = genesis.hash()?;
+        Ok(Self {
+            consensus,
+            blocks: vec![genesis],
+            heights: BTreeMap::from([(Arc::clone(&hash), 0)]),
+            hashes: vec![hash],
+        })
+    }
+
+    /// Height of the tip, 0 when only genesis is present
+    #[must_use]
+    pub const fn get_height(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    /// Get the newest block
+    #[must_use]
+    pub fn get_tip(&self) -> &Block {
+        &self.blocks[self.blocks.len() - 1]
+    }
+
+    /// Get the hash of the newest block
+    #[must_use]
+    pub fn get_tip_hash(&self) -> &Arc<Hash> {
+        &self.hashes[self.hashes.len() - 1]
+    }
+
+    /// Get the genesis block
+    #[must_use]
+    pub fn get_genesis(&self) -> &Block {
+        &self.blocks[0]
+    }
+
+    /// Get the block at `height`
+    #[must_use]
+    pub fn get_by_height(&self, height: u64) -> Option<&Block> {
+        usize::try_from(height)
+            .ok()
+            .and_then(|height| self.blocks.get(height))
+    }
+
+    /// Get the block with `hash`
+    #[must_use]
+    pub fn get_by_hash(&self, hash: &Hash) -> Option<&Block> {
+        self.get_height_of(hash)
+            .and_then(|height| self.get_by_height(height))
+    }
+
+    /// Height of the block with `hash`
+    #[must_use]
+    pub fn get_height_of(&self, hash: &Hash) -> Option<u64> {
+        self.heights.get(hash).copied()
+    }
+
+    /// Get the signers blocks are accepted from
+    #[must_use]
+    pub const fn get_consensus(&self) -> &Consensus {
+        &self.consensus
+    }
+
+    /// Check that `block` could be appended at `now`
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason the block would be rejected
+    pub fn check(&self, block: &Block, now: &DateTime<Utc>) -> Result<(), ChainError> {
+        if let Some(height) = self.get_height_of(&*block.hash()?) {
+            return Err(ChainError::Duplicate(height));
+        }
+        let parent_height = self
+            .get_height_of(block.get_previous_block_hash())
+            .ok_or(ChainError::UnknownParent)?;
+        if parent_height != self.get_height() {
+            return Err(ChainError::NotOnTip {
+                parent_height,
+                tip_height: self.get_height(),
+            });
+        }
+
+        let tip = self.get_tip().get_time();
+        if block.get_time() <= tip {
+            return Err(ChainError::TimeNotIncreasing {
+                tip: *tip,
+                found: *block.get_time(),
+            });
+        }
+        let slot = Slot::from_time(block.get_time());
+        if slot == Slot::from_time(tip) {
+            return Err(ChainError::SlotTaken(slot));
+        }
+        let current = Slot::from_time(now);
+        if slot > current {
+            return Err(ChainError::FutureSlot {
+                current,
+                found: slot,
+            });
+        }
+
+        self.consensus
+            .check_candidate(block, self.get_tip(), slot)?;
+        Ok(())
+    }
+
+    /// Append `block` as the new tip and apply its transactions to `ledger`,
+    /// returning its hash
+    ///
+    /// The block is checked before the ledger is touched, and the ledger undoes
+    /// a block it cannot fully apply.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason the block was rejected, leaving the chain and
+    /// ledger unchanged
+    pub fn append(
+        &mut self,
+        block: Block,
+        ledger: &mut Ledger,
+        now: &DateTime<Utc>,
+    ) -> Result<Arc<Hash>, ChainError> {
+        self.check(&block, now)?;
+        let hash = block.hash()?;
+        let proposer = block
+            .get_proposer()
+            .ok_or(ConsensusError::Block(BlockError::Unsigned))?;
+        ledger.apply_block(
+            proposer,
+            block
+                .get_transactions()
+                .iter()
+                .map(SignedTransaction::get_transaction),
+            block.get_time(),
+        )?;
+        self.heights
+            .insert(Arc::clone(&hash), self.blocks.len() as u64);
+        self.hashes.push(Arc::clone(&hash));
+        self.blocks.push(block);
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::LedgerError;
+    use crate::test_util::Proposer;
+    use crate::transactions::Transaction;
+    use chrono::TimeDelta;
+    use std::rc::Rc;
+
+    fn chain(proposer: &Proposer) -> Chain {
+        Chain::new(
+            Block::default(),
+            Consensus::new([Rc::clone(&proposer.address)]),
+        )
+        .unwrap_or_else(|e| panic!("chain: {e}"))
+    }
+
+    #[test]
+    fn test_append_and_index() {
+        let proposer = Proposer::new();
+        let mut chain = chain(&proposer);
+        let mut ledger = Ledger::new();
+        let genesis = Arc::clone(chain.get_tip_hash());
+        let now = Utc::now();
+        let start = Slot::from_time(&now).start() - TimeDelta::seconds(3 * 600);
+
+        let first = chain
+            .append(proposer.propose(start, chain.get_tip()), &mut ledger, &now)
+            .unwrap_or_else(|e| panic!("append: {e}"));
+        let next = proposer.propose(start + TimeDelta::seconds(1205), chain.get_tip());
+        let second = chain
+            .append(next, &mut ledger, &now)
+            .unwrap_or_else(|e| panic!("append: {e}"));
+
+        assert_eq!(chain.get_height(), 2);
+        assert_eq!(chain.get_tip_hash(), &second);
+        assert_eq!(chain.get_height_of(&first), Some(1));
+        assert_eq!(chain.get_height_of(&genesis), Some(0));
+        assert_eq!(
+            chain
+                .get_by_hash(&first)
+                .map(Block::get_previous_block_hash),
+            Some(&genesis)
+        );
+        assert_eq!(
+            chain.get_by_height(2).map(Block::hash).and_then(Result::ok),
+            Some(second)
+        );
+        assert!(chain.get_by_height(3).is_none());
+        assert_eq!(chain.get_genesis(), &Block::default());
+    }
+
+    #[test]
+    fn test_rejection_reasons() {
+        let proposer = Proposer::new();
+        let mut chain = chain(&proposer);
+        let mut ledger = Ledger::new();
+        let genesis = Block::default();
+        let now = Utc::now();
+        let slot = Slot::from_time(&now);
+        let start = slot.start() - TimeDelta::seconds(3 * 600);
+
+        let first = proposer.propose(start + TimeDelta::seconds(10), &genesis);
+        chain
+            .append(
+                proposer.propose(start + TimeDelta::seconds(10), &genesis),
+                &mut ledger,
+                &now,
+            )
+            .unwrap_or_else(|e| panic!("append: {e}"));
+        assert_eq!(chain.check(&first, &now), Err(ChainError::Duplicate(1)));
+
+        // builds on a block the chain has never seen
+        let orphan = proposer.propose(start + TimeDelta::seconds(600), &first);
+        let stranger = proposer.propose(start + TimeDelta::seconds(600), &orphan);
+        assert_eq!(chain.check(&stranger, &now), Err(ChainError::UnknownParent));
+
+        let fork = proposer.propose(start + TimeDelta::seconds(600), &genesis);
+        assert_eq!(
+            chain.check(&fork, &now),
+            Err(ChainError::NotOnTip {
+                parent_height: 0,
+                tip_height: 1
+            })
+        );
+
+        let earlier = proposer.propose(start + TimeDelta::seconds(5), &first);
+        assert!(matches!(
+            chain.check(&earlier, &now),
+            Err(ChainError::TimeNotIncreasing { .. })
+        ));
+
+        let same_slot = proposer.propose(start + TimeDelta::seconds(20), &first);
+        assert_eq!(
+            chain.check(&same_slot, &now),
+            Err(ChainError::SlotTaken(Slot::from_time(&start)))
+        );
+
+        let future = proposer.propose(slot.end(), &first);
+        assert_eq!(
+            chain.check(&future, &now),
+            Err(ChainError::FutureSlot {
+                current: slot,
+                found: slot.next()
+            })
+        );
+
+        let outsider = Proposer::new().propose(start + TimeDelta::seconds(600), &first);
+        assert!(matches!(
+            chain.check(&outsider, &now),
+            Err(ChainError::Rejected(ConsensusError::NotEligible(_)))
+        ));
+
+        // nothing above changed the chain
+        assert_eq!(chain.get_height(), 1);
+        assert!(chain
+            .append(
+                proposer.propose(start + TimeDelta::seconds(600), &first),
+                &mut ledger,
+                &now
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_ledger_moves_with_chain() {
+        let proposer = Proposer::new();
+        let bob = Proposer::new();
+        let mut chain = chain(&proposer);
+        let mut ledger = Ledger::new();
+        ledger
+            .credit(&proposer.address, 100)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+        let now = Utc::now();
+        let start = Slot::from_time(&now).start() - TimeDelta::seconds(3 * 600);
+        let pay = |amount, nonce| {
+            let transaction = Transaction::new(
+                Rc::clone(&proposer.address),
+                Rc::clone(&bob.address),
+                amount,
+                start,
+                nonce,
+            )
+            .with_fee(2);
+            SignedTransaction::new(transaction, Arc::clone(&proposer.signer))
+                .unwrap_or_else(|e| panic!("sign: {e}"))
+        };
+
+        let block = proposer.propose_with(start, chain.get_tip(), vec![pay(10, 0)]);
+        chain
+            .append(block, &mut ledger, &now)
+            .unwrap_or_else(|e| panic!("append: {e}"));
+        // the proposer collects its own fee back
+        assert_eq!(ledger.get_balance(&proposer.address), 90);
+        assert_eq!(ledger.get_balance(&bob.address), 10);
+
+        // a block the ledger refuses is not appended
+        let time = start + TimeDelta::seconds(600);
+        let overdraft = proposer.propose_with(time, chain.get_tip(), vec![pay(1_000, 1)]);
+        assert!(matches!(
+            chain.append(overdraft, &mut ledger, &now),
+            Err(ChainError::Ledger(LedgerError::Overdraft { .. }))
+        ));
+        assert_eq!(chain.get_height(), 1);
+
+        // a block the chain refuses leaves the ledger alone
+        let outsider = bob.propose_with(time, chain.get_tip(), vec![pay(10, 1)]);
+        assert!(matches!(
+            chain.append(outsider, &mut ledger, &now),
+            Err(ChainError::Rejected(ConsensusError::NotEligible(_)))
+        ));
+        assert_eq!(ledger.get_balance(&proposer.address), 90);
+        assert_eq!(ledger.get_next_nonce(&proposer.address), 1);
+        assert_eq!(chain.get_height(), 1);
+    }
+}
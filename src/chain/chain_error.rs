@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use std::fmt::Display;
+
+use crate::consensus::{ConsensusError, Slot};
+use crate::ledger::LedgerError;
+
+/// Why the chain refused a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    /// The block is already in the chain at this height
+    Duplicate(u64),
+    /// The previous block hash is not in the chain
+    UnknownParent,
+    /// The block builds on a block below the tip
+    NotOnTip {
+        /// Height of the block it builds on
+        parent_height: u64,
+        /// Height of the tip
+        tip_height: u64,
+    },
+    /// The block time is not after the tip's
+    TimeNotIncreasing {
+        /// Time of the tip
+        tip: DateTime<Utc>,
+        /// Time of the block
+        found: DateTime<Utc>,
+    },
+    /// The tip already fills the block's slot
+    SlotTaken(Slot),
+    /// The block's slot has not started yet
+    FutureSlot {
+        /// Slot at the time of checking
+        current: Slot,
+        /// Slot of the block time
+        found: Slot,
+    },
+    /// The block is not a valid candidate for its slot
+    Rejected(ConsensusError),
+    /// The block's transactions cannot be applied to the ledger
+    Ledger(LedgerError),
+    /// A block could not be encoded or hashed
+    Serialise(String),
+}
+
+impl Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Duplicate(height) => write!(f, "Block is already at height {height}"),
+            Self::UnknownParent => write!(f, "Previous block is not in the chain"),
+            Self::NotOnTip {
+                parent_height,
+                tip_height,
+            } => write!(
+                f,
+                "Block builds on height {parent_height}, the tip is at height {tip_height}"
+            ),
+            Self::TimeNotIncreasing { tip, found } => {
+                write!(f, "Block time {found} is not after the tip's {tip}")
+            }
+            Self::SlotTaken(slot) => write!(f, "Slot {slot} already has a block"),
+            Self::FutureSlot { current, found } => {
+                write!(
+                    f,
+                    "Block is for slot {found}, the current slot is {current}"
+                )
+            }
+            Self::Rejected(e) => write!(f, "Block rejected: {e}"),
+            Self::Ledger(e) => write!(f, "Block transactions rejected: {e}"),
+            Self::Serialise(reason) => write!(f, "Block could not be encoded: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+impl From<ConsensusError> for ChainError {
+    fn from(value: ConsensusError) -> Self {
+        Self::Rejected(value)
+    }
+}
+
+impl From<LedgerError> for ChainError {
+    fn from(value: LedgerError) -> Self {
+        Self::Ledger(value)
+    }
+}
+
+impl From<base_xx::SerialiseError> for ChainError {
+    fn from(value: base_xx::SerialiseError) -> Self {
+        Self::Serialise(value.to_string())
+    }
+}
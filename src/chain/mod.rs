@@ -0,0 +1,9 @@
+/// Append-only chain of blocks
+#[allow(clippy::module_inception)]
+pub mod chain;
+
+/// Chain errors
+pub mod chain_error;
+
+pub use chain::Chain;
+pub use chain_error::ChainError;
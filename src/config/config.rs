@@ -1,28 +1,61 @@
 use std::cell::RefCell;
 
+use crate::genesis::Network;
+
+/// Node configuration
 pub struct Config {
     db_path: String,
+    network: Network,
+    genesis_path: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             db_path: "db".to_string(),
+            network: Network::default(),
+            genesis_path: None,
         }
     }
 }
 
 impl Config {
-    pub fn get_db_path(&self) -> &String {
+    /// Directory holding the node's data
+    #[must_use]
+    pub const fn get_db_path(&self) -> &String {
         &self.db_path
     }
 
+    /// Set the directory holding the node's data
     pub fn set_db_path(&mut self, db_path: &str) {
         self.db_path = db_path.to_string();
     }
+
+    /// Network the node joins
+    #[must_use]
+    pub const fn get_network(&self) -> Network {
+        self.network
+    }
+
+    /// Set the network the node joins
+    pub const fn set_network(&mut self, network: Network) {
+        self.network = network;
+    }
+
+    /// Genesis file overriding the network's preset specification
+    #[must_use]
+    pub fn get_genesis_path(&self) -> Option<&str> {
+        self.genesis_path.as_deref()
+    }
+
+    /// Set the genesis file, or `None` to use the network's preset
+    pub fn set_genesis_path(&mut self, genesis_path: Option<String>) {
+        self.genesis_path = genesis_path;
+    }
 }
 
 thread_local! {
+    /// Configuration for the current thread
     pub static CONFIG: RefCell<Config> = RefCell::new(Config::default());
 }
 
@@ -34,6 +67,8 @@ mod tests {
     fn test_config() {
         CONFIG.with(|config| {
             assert_eq!(config.borrow().get_db_path(), "db");
+            assert_eq!(config.borrow().get_network(), Network::Devnet);
+            assert_eq!(config.borrow().get_genesis_path(), None);
         });
     }
 
@@ -0,0 +1,379 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use simple_sign::Ed25519Signer;
+
+use crate::address::public_address::PublicAddress;
+use crate::address::KeyAlgorithm;
+use crate::consensus::{ConsensusError, Slot, VrfProof};
+use crate::game::{Block, BlockError};
+
+/// Signers allowed to propose blocks, and the rules for choosing between them
+///
+/// Each slot, every eligible signer may propose a block carrying a ticket:
+/// its VRF proof over the slot seed. The valid candidate with the lowest VRF
+/// output wins the slot. A key has exactly one output per seed, and each seed
+/// is chained from the parent's VRF output rather than its hash, so neither
+/// the current proposer nor the parent's can improve their chances by
+/// changing the contents of a block. Only Ed25519 keys are eligible, as the
+/// VRF is defined over them.
+///
+/// Between forks, the chain with the most blocks is canonical; chains of
+/// equal length are compared block by block and the first lower ticket wins.
+/// Every node holding the same blocks therefore picks the same chain.
+#[derive(Debug, Default)]
+pub struct Consensus {
+    signers: BTreeSet<Rc<PublicAddress>>,
+}
+
+impl Consensus {
+    /// Consensus among `signers`
+    #[must_use]
+    pub fn new(signers: impl IntoIterator<Item = Rc<PublicAddress>>) -> Self {
+        Self {
+            signers: signers.into_iter().collect(),
+        }
+    }
+
+    /// Whether `address` may propose blocks
+    #[must_use]
+    pub fn is_eligible(&self, address: &PublicAddress) -> bool {
+        address.get_algorithm() == KeyAlgorithm::Ed25519 && self.signers.contains(address)
+    }
+
+    /// Draw `signer`'s ticket for `slot`, building on `parent`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed cannot be hashed or proved
+    pub fn draw_ticket(
+        signer: &Ed25519Signer,
+        slot: Slot,
+        parent: &Block,
+    ) -> Result<VrfProof, ConsensusError> {
+        let seed = slot.seed(parent)?;
+        VrfProof::prove(signer, seed.get_bytes().get_bytes()).ok_or(ConsensusError::BadTicket)
+    }
+
+    /// Check that `block` is a valid candidate for `slot`, building on `parent`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the block is invalid, does not build on `parent`,
+    /// is made outside the slot, proposed by an ineligible signer or carries a
+    /// bad ticket
+    pub fn check_candidate(
+        &self,
+        block: &Block,
+        parent: &Block,
+        slot: Slot,
+    ) -> Result<(), ConsensusError> {
+        block.verify()?;
+        if *block.get_previous_block_hash() != parent.hash()? {
+            return Err(ConsensusError::WrongParent);
+        }
+        let found = Slot::from_time(block.get_time());
+        if found != slot {
+            return Err(ConsensusError::WrongSlot {
+                expected: slot,
+                found,
+            });
+        }
+        let Some(proposer) = block.get_proposer() else {
+            return Err(ConsensusError::Block(BlockError::Unsigned));
+        };
+        if !self.is_eligible(proposer) {
+            return Err(ConsensusError::NotEligible(proposer.to_string()));
+        }
+        let ticket = block.get_ticket().ok_or(ConsensusError::MissingTicket)?;
+        let seed = slot.seed(parent)?;
+        if ticket
+            .verify(proposer, seed.get_bytes().get_bytes())
+            .is_none()
+        {
+            return Err(ConsensusError::BadTicket);
+        }
+        Ok(())
+    }
+
+    /// Order two blocks by VRF output, `Less` meaning `a` wins
+    ///
+    /// Blocks without a ticket lose to any with one. Equal outputs, which
+    /// only the same proposer can hold, fall back to the block hash.
+    #[must_use]
+    pub fn compare_tickets(a: &Block, b: &Block) -> Ordering {
+        let key = |block: &Block| {
+            (
+                block.get_ticket().and_then(VrfProof::output),
+                block.hash().ok(),
+            )
+        };
+        let ((a_ticket, a_hash), (b_ticket, b_hash)) = (key(a), key(b));
+        match (a_ticket, b_ticket) {
+            (Some(a_ticket), Some(b_ticket)) => a_ticket.cmp(&b_ticket),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| a_hash.cmp(&b_hash))
+    }
+
+    /// The winning candidate for `slot` building on `parent`, ignoring invalid
+    /// ones
+    #[must_use]
+    pub fn select_winner<'a>(
+        &self,
+        candidates: &'a [Block],
+        parent: &Block,
+        slot: Slot,
+    ) -> Option<&'a Block> {
+        candidates
+            .iter()
+            .filter(|block| self.check_candidate(block, parent, slot).is_ok())
+            .min_by(|a, b| Self::compare_tickets(a, b))
+    }
+
+    /// Order two chains, `Greater` meaning `a` is preferred
+    ///
+    /// Both chains must start from the same block. The longer chain is
+    /// preferred; otherwise the first block where they differ decides by
+    /// ticket.
+    #[must_use]
+    pub fn compare_chains(a: &[Block], b: &[Block]) -> Ordering {
+        a.len().cmp(&b.len()).then_with(|| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| Self::compare_tickets(b, a))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+
+    /// The canonical chain among `chains`, already checked block by block
+    #[must_use]
+    pub fn fork_choice<'a>(&self, chains: &'a [Vec<Block>]) -> Option<&'a [Block]> {
+        chains
+            .iter()
+            .max_by(|a, b| Self::compare_chains(a, b))
+            .map(Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Proposer;
+    use crate::transactions::{SignedTransaction, Transaction};
+    use base_xx::ByteVec;
+    use chrono::TimeDelta;
+    use simple_sign::Secp256k1Signer;
+    use slahasher::Hash;
+    use std::sync::Arc;
+
+    fn hash(block: &Block) -> Arc<Hash> {
+        block.hash().unwrap_or_else(|e| panic!("hash: {e}"))
+    }
+
+    #[test]
+    fn test_lowest_ticket_wins() {
+        let proposers: Vec<_> = (0..4).map(|_| Proposer::new()).collect();
+        let outsider = Proposer::new();
+        let consensus = Consensus::new(proposers.iter().map(|p| Rc::clone(&p.address)));
+        let genesis = Block::default();
+        let slot = Slot::new(10);
+        let time = slot.start() + TimeDelta::seconds(30);
+
+        let candidates: Vec<_> = proposers
+            .iter()
+            .map(|proposer| proposer.propose(time, &genesis))
+            .collect();
+        let lowest = candidates
+            .iter()
+            .min_by_key(|block| block.get_ticket().and_then(VrfProof::output))
+            .unwrap_or_else(|| unreachable!());
+        let winner = consensus
+            .select_winner(&candidates, &genesis, slot)
+            .unwrap_or_else(|| unreachable!());
+        assert_eq!(winner, lowest);
+
+        // the ticket survives the block encoding
+        let bytes = ByteVec::try_from(winner).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = Block::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(&decoded, winner);
+        assert_eq!(consensus.check_candidate(&decoded, &genesis, slot), Ok(()));
+
+        // the order candidates arrive in does not matter
+        let reversed: Vec<_> = proposers
+            .iter()
+            .rev()
+            .map(|proposer| proposer.propose(time, &genesis))
+            .collect();
+        assert_eq!(
+            consensus.select_winner(&reversed, &genesis, slot),
+            Some(lowest)
+        );
+
+        let intruder = outsider.propose(time, &genesis);
+        assert!(matches!(
+            consensus.check_candidate(&intruder, &genesis, slot),
+            Err(ConsensusError::NotEligible(_))
+        ));
+    }
+
+    #[test]
+    fn test_candidate_checks() {
+        let proposer = Proposer::new();
+        let consensus = Consensus::new([Rc::clone(&proposer.address)]);
+        let genesis = Block::default();
+        let slot = Slot::new(10);
+        let time = slot.start();
+
+        assert_eq!(
+            consensus.check_candidate(&proposer.propose(time, &genesis), &genesis, slot.next()),
+            Err(ConsensusError::WrongSlot {
+                expected: slot.next(),
+                found: slot
+            })
+        );
+
+        // a ticket drawn for another slot
+        let stale = Consensus::draw_ticket(&proposer.signer, slot.next(), &genesis)
+            .unwrap_or_else(|e| panic!("ticket: {e}"));
+        let mut block = Block::new(
+            time,
+            hash(&genesis),
+            Rc::clone(&proposer.address),
+            Vec::new(),
+        )
+        .unwrap_or_else(|e| panic!("block: {e}"))
+        .with_ticket(stale);
+        block
+            .sign(Arc::clone(&proposer.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(
+            consensus.check_candidate(&block, &genesis, slot),
+            Err(ConsensusError::BadTicket)
+        );
+
+        let mut untimed = Block::new(
+            time,
+            hash(&genesis),
+            Rc::clone(&proposer.address),
+            Vec::new(),
+        )
+        .unwrap_or_else(|e| panic!("block: {e}"));
+        assert_eq!(
+            consensus.check_candidate(&untimed, &genesis, slot),
+            Err(ConsensusError::Block(BlockError::Unsigned))
+        );
+        untimed
+            .sign(Arc::clone(&proposer.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(
+            consensus.check_candidate(&untimed, &genesis, slot),
+            Err(ConsensusError::MissingTicket)
+        );
+
+        // the VRF needs an Ed25519 key
+        let secp = Secp256k1Signer::new_random();
+        let address = Rc::new(PublicAddress::try_from(&secp).unwrap_or_else(|_| unreachable!()));
+        let consensus = Consensus::new([Rc::clone(&address)]);
+        assert!(!consensus.is_eligible(&address));
+    }
+
+    #[test]
+    fn test_seed_ignores_block_contents() {
+        let proposer = Proposer::new();
+        let consensus = Consensus::new([Rc::clone(&proposer.address)]);
+        let genesis = Block::default();
+        let slot = Slot::new(10);
+        let time = slot.start();
+
+        // the same ticket on a block with different transactions
+        let empty = proposer.propose(time, &genesis);
+        let ticket = Consensus::draw_ticket(&proposer.signer, slot, &genesis)
+            .unwrap_or_else(|e| panic!("ticket: {e}"));
+        let transaction = Transaction::new(
+            Rc::clone(&proposer.address),
+            Rc::clone(&proposer.address),
+            1,
+            time,
+            0,
+        );
+        let signed = SignedTransaction::new(transaction, Arc::clone(&proposer.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        let mut full = Block::new(
+            time,
+            hash(&genesis),
+            Rc::clone(&proposer.address),
+            vec![signed],
+        )
+        .unwrap_or_else(|e| panic!("block: {e}"))
+        .with_ticket(ticket);
+        full.sign(Arc::clone(&proposer.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(consensus.check_candidate(&full, &genesis, slot), Ok(()));
+        assert_ne!(hash(&empty), hash(&full));
+
+        let seed = |parent: &Block| {
+            slot.next()
+                .seed(parent)
+                .unwrap_or_else(|e| panic!("seed: {e}"))
+        };
+        assert_eq!(seed(&empty), seed(&full));
+
+        // a child checked against the wrong parent is refused
+        let child = proposer.propose(slot.next().start(), &empty);
+        assert_eq!(
+            consensus.check_candidate(&child, &full, slot.next()),
+            Err(ConsensusError::WrongParent)
+        );
+    }
+
+    #[test]
+    fn test_fork_choice() {
+        let (alice, bob) = (Proposer::new(), Proposer::new());
+        let consensus = Consensus::new([Rc::clone(&alice.address), Rc::clone(&bob.address)]);
+        let genesis = Block::default();
+        let slot = Slot::new(100);
+
+        let first = alice.propose(slot.start(), &genesis);
+        let fork_a = alice.propose(slot.next().start(), &first);
+        let fork_b = bob.propose(slot.next().start(), &first);
+        let a_wins = Consensus::compare_tickets(&fork_a, &fork_b) == Ordering::Less;
+        let (winning, losing) = if a_wins {
+            (&alice, &bob)
+        } else {
+            (&bob, &alice)
+        };
+
+        let chain = |tip: &Proposer, slots: u64| {
+            let mut blocks = vec![alice.propose(slot.start(), &genesis)];
+            let mut time = slot.start();
+            for _ in 0..slots {
+                time += TimeDelta::seconds(600);
+                let parent = blocks.last().unwrap_or_else(|| unreachable!());
+                blocks.push(tip.propose(time, parent));
+            }
+            blocks
+        };
+
+        // equal length: the lower ticket at the fork wins, whatever the order
+        let chains = vec![chain(losing, 1), chain(winning, 1)];
+        let canonical = consensus
+            .fork_choice(&chains)
+            .unwrap_or_else(|| unreachable!());
+        assert_eq!(canonical, chains[1].as_slice());
+        let swapped = vec![chain(winning, 1), chain(losing, 1)];
+        assert_eq!(consensus.fork_choice(&swapped), Some(swapped[0].as_slice()));
+
+        // a longer chain beats a better ticket
+        let chains = vec![chain(winning, 1), chain(losing, 2)];
+        assert_eq!(consensus.fork_choice(&chains), Some(chains[1].as_slice()));
+        assert_eq!(
+            Consensus::compare_chains(&chains[0], &chains[0]),
+            Ordering::Equal
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt::Display;
+
+use crate::consensus::Slot;
+use crate::game::BlockError;
+
+/// Why a block cannot stand as a slot candidate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsensusError {
+    /// The block itself is invalid
+    Block(BlockError),
+    /// The proposer is not an eligible signer
+    NotEligible(String),
+    /// The block does not build on the parent it was checked against
+    WrongParent,
+    /// The block carries no lottery ticket
+    MissingTicket,
+    /// The ticket is not the proposer's VRF proof over the slot seed
+    BadTicket,
+    /// The block time falls outside the slot
+    WrongSlot {
+        /// Slot being decided
+        expected: Slot,
+        /// Slot of the block time
+        found: Slot,
+    },
+    /// The slot seed could not be computed
+    Serialise(String),
+}
+
+impl Display for ConsensusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Block(e) => write!(f, "Invalid block: {e}"),
+            Self::NotEligible(address) => write!(f, "{address} is not an eligible signer"),
+            Self::WrongParent => write!(f, "Block does not build on the given parent"),
+            Self::MissingTicket => write!(f, "Block has no lottery ticket"),
+            Self::BadTicket => write!(f, "Ticket is not the proposer's proof over the seed"),
+            Self::WrongSlot { expected, found } => {
+                write!(
+                    f,
+                    "Expected a block for slot {expected}, found slot {found}"
+                )
+            }
+            Self::Serialise(reason) => write!(f, "Seed could not be computed: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConsensusError {}
+
+impl From<BlockError> for ConsensusError {
+    fn from(value: BlockError) -> Self {
+        Self::Block(value)
+    }
+}
+
+impl From<base_xx::SerialiseError> for ConsensusError {
+    fn from(value: base_xx::SerialiseError) -> Self {
+        Self::Serialise(value.to_string())
+    }
+}
@@ -0,0 +1,17 @@
+/// Block selection and fork choice
+#[allow(clippy::module_inception)]
+pub mod consensus;
+
+/// Consensus errors
+pub mod consensus_error;
+
+/// 600-second block slots
+pub mod slot;
+
+/// Verifiable random function over Ed25519 keys
+pub mod vrf;
+
+pub use consensus::Consensus;
+pub use consensus_error::ConsensusError;
+pub use slot::Slot;
+pub use vrf::VrfProof;
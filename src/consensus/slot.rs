@@ -0,0 +1,143 @@
+use base_xx::{ByteVec, SerialiseError};
+use chrono::{DateTime, TimeZone, Utc};
+use slahasher::{Hash, HashAlgorithm};
+use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::consensus::VrfProof;
+use crate::game::Block;
+
+/// Length of a slot in seconds
+pub const SLOT_SECS: u64 = 600;
+
+/// Prefix of the hashed seed
+const SEED_PREFIX: &[u8] = b"slot";
+
+/// A 600-second window of time in which at most one block is added
+///
+/// Slot `n` covers the Unix seconds `[n * SLOT_SECS, (n + 1) * SLOT_SECS)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Slot(u64);
+
+impl Slot {
+    /// Slot with index `index`
+    #[must_use]
+    pub const fn new(index: u64) -> Self {
+        Self(index)
+    }
+
+    /// Slot containing `time`; times before the Unix epoch fall in slot 0
+    #[must_use]
+    pub fn from_time(time: &DateTime<Utc>) -> Self {
+        Self(u64::try_from(time.timestamp()).unwrap_or(0) / SLOT_SECS)
+    }
+
+    /// Get the slot index
+    #[must_use]
+    pub const fn get_index(&self) -> u64 {
+        self.0
+    }
+
+    /// The slot after this one
+    #[must_use]
+    pub const fn next(&self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    /// First second of the slot
+    #[must_use]
+    pub fn start(&self) -> DateTime<Utc> {
+        Self::at(self.0.saturating_mul(SLOT_SECS))
+    }
+
+    /// First second of the next slot
+    #[must_use]
+    pub fn end(&self) -> DateTime<Utc> {
+        Self::at(self.0.saturating_add(1).saturating_mul(SLOT_SECS))
+    }
+
+    fn at(seconds: u64) -> DateTime<Utc> {
+        i64::try_from(seconds)
+            .ok()
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Whether `time` falls within the slot
+    #[must_use]
+    pub fn contains(&self, time: &DateTime<Utc>) -> bool {
+        Self::from_time(time) == *self && time.timestamp() >= 0
+    }
+
+    /// Seed that proposers sign to enter the lottery for this slot
+    ///
+    /// KECCAK512 of `"slot"`, the big-endian slot index and the VRF output of
+    /// the ticket of `parent`, the block being built on. The output is fixed by
+    /// the parent's proposer key and seed alone, so no proposer can steer the
+    /// next draw by choosing which transactions to include. The genesis block
+    /// has no ticket, so its hash stands in; it commits only to the network's
+    /// fixed specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the seed cannot be hashed
+    pub fn seed(&self, parent: &Block) -> Result<Arc<Hash>, SerialiseError> {
+        let randomness = match parent.get_ticket().and_then(VrfProof::output) {
+            Some(output) => output.to_vec(),
+            None => parent.hash()?.get_bytes().get_bytes().to_vec(),
+        };
+        let bytes = [SEED_PREFIX, &self.0.to_be_bytes(), &randomness].concat();
+        Hash::try_hash(
+            Arc::new(ByteVec::new(bytes.into())),
+            HashAlgorithm::KECCAK512,
+        )
+    }
+}
+
+impl Display for Slot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn test_slot_windows() {
+        let base = Slot::new(12345).start();
+        assert_eq!(base.timestamp(), 600 * 12345);
+        let inside = base + TimeDelta::minutes(5);
+        assert_eq!(Slot::from_time(&inside), Slot::new(12345));
+        assert!(Slot::new(12345).contains(&inside));
+
+        let last = Slot::new(12345).end() - TimeDelta::seconds(1);
+        assert!(Slot::new(12345).contains(&last));
+        assert!(!Slot::new(12345).contains(&Slot::new(12345).end()));
+        assert_eq!(Slot::new(12345).next().start(), Slot::new(12345).end());
+
+        let before_epoch = DateTime::<Utc>::default() - TimeDelta::seconds(1);
+        assert_eq!(Slot::from_time(&before_epoch), Slot::new(0));
+        assert!(!Slot::new(0).contains(&before_epoch));
+    }
+
+    #[test]
+    fn test_seed() {
+        let genesis = Block::default();
+        let other = Block::genesis(
+            Slot::new(1).start(),
+            genesis.hash().unwrap_or_else(|e| panic!("hash: {e}")),
+        )
+        .unwrap_or_else(|e| panic!("genesis: {e}"));
+        let seed = |slot: u64, parent: &Block| {
+            Slot::new(slot)
+                .seed(parent)
+                .unwrap_or_else(|e| panic!("seed: {e}"))
+        };
+        assert_eq!(seed(7, &genesis), seed(7, &genesis));
+        assert_ne!(seed(7, &genesis), seed(8, &genesis));
+        assert_ne!(seed(7, &genesis), seed(7, &other));
+    }
+}
@@ -0,0 +1,278 @@
+use base_xx::{ByteVec, SerialiseError};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+use simple_sign::Ed25519Signer;
+use std::rc::Rc;
+
+use crate::address::public_address::PublicAddress;
+use crate::address::KeyAlgorithm;
+use crate::serialise::{FieldType, RLEByteVec, RLEFieldIter, RleDecodeField, RleEncodeField};
+
+/// Length of an encoded proof: `Gamma`, `c` and `s`
+pub const VRF_PROOF_LEN: usize = 80;
+
+/// Length of a VRF output
+pub const VRF_OUTPUT_LEN: usize = 64;
+
+/// ECVRF-EDWARDS25519-SHA512-TAI
+const SUITE: u8 = 0x03;
+
+const C_LEN: usize = 16;
+
+/// An RFC 9381 ECVRF-EDWARDS25519-SHA512-TAI proof
+///
+/// Made with an Ed25519 key over an input `alpha`, the proof yields a 64-byte
+/// output that anyone holding the public key can check. Unlike a signature,
+/// each key has exactly one valid proof and output per input, so the output
+/// cannot be re-rolled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VrfProof {
+    gamma: [u8; 32],
+    c: [u8; C_LEN],
+    s: [u8; 32],
+}
+
+impl VrfProof {
+    /// Prove `alpha` with `signer`'s key
+    ///
+    /// Returns `None` only if no try-and-increment counter maps the input to a
+    /// curve point, which happens with negligible probability.
+    #[must_use]
+    #[allow(clippy::many_single_char_names)] // names follow RFC 9381
+    pub fn prove(signer: &Ed25519Signer, alpha: &[u8]) -> Option<Self> {
+        let key = signer.get_signing_key();
+        let expanded = Sha512::digest(key.as_bytes());
+        let mut clamped = [0u8; 32];
+        clamped.copy_from_slice(&expanded[..32]);
+        clamped[0] &= 0b1111_1000;
+        clamped[31] &= 0b0111_1111;
+        clamped[31] |= 0b0100_0000;
+        let x = Scalar::from_bytes_mod_order(clamped);
+        let public_key = EdwardsPoint::mul_base(&x);
+        let public_bytes = public_key.compress().to_bytes();
+
+        let h = encode_to_curve(&public_bytes, alpha)?;
+        let h_bytes = h.compress().to_bytes();
+        let gamma = x * h;
+        let k = Scalar::from_bytes_mod_order_wide(
+            &Sha512::new()
+                .chain_update(&expanded[32..])
+                .chain_update(h_bytes)
+                .finalize()
+                .into(),
+        );
+        let c = challenge(&[
+            &public_key,
+            &h,
+            &gamma,
+            &EdwardsPoint::mul_base(&k),
+            &(k * h),
+        ]);
+        let s = k + c * x;
+
+        let mut c_bytes = [0u8; C_LEN];
+        c_bytes.copy_from_slice(&c.to_bytes()[..C_LEN]);
+        Some(Self {
+            gamma: gamma.compress().to_bytes(),
+            c: c_bytes,
+            s: s.to_bytes(),
+        })
+    }
+
+    /// Check the proof against `public_key` and `alpha`, returning the output
+    ///
+    /// Returns `None` if the proof is invalid or the key is not a valid
+    /// Ed25519 key.
+    #[must_use]
+    #[allow(clippy::many_single_char_names)] // names follow RFC 9381
+    pub fn verify(&self, public_key: &PublicAddress, alpha: &[u8]) -> Option<[u8; VRF_OUTPUT_LEN]> {
+        if public_key.get_algorithm() != KeyAlgorithm::Ed25519 {
+            return None;
+        }
+        let public_bytes: [u8; 32] = public_key.get_public_key().get_bytes().try_into().ok()?;
+        let y = CompressedEdwardsY(public_bytes).decompress()?;
+        if y.is_small_order() {
+            return None;
+        }
+        let gamma = CompressedEdwardsY(self.gamma).decompress()?;
+        let s = Option::<Scalar>::from(Scalar::from_canonical_bytes(self.s))?;
+        let c = self.challenge_scalar();
+
+        let h = encode_to_curve(&public_bytes, alpha)?;
+        let u = EdwardsPoint::mul_base(&s) - c * y;
+        let v = s * h - c * gamma;
+        if challenge(&[&y, &h, &gamma, &u, &v]) != c {
+            return None;
+        }
+        Some(output(&gamma))
+    }
+
+    /// The output, without checking the proof
+    #[must_use]
+    pub fn output(&self) -> Option<[u8; VRF_OUTPUT_LEN]> {
+        CompressedEdwardsY(self.gamma)
+            .decompress()
+            .map(|gamma| output(&gamma))
+    }
+
+    fn challenge_scalar(&self) -> Scalar {
+        let mut bytes = [0u8; 32];
+        bytes[..C_LEN].copy_from_slice(&self.c);
+        Scalar::from_bytes_mod_order(bytes)
+    }
+
+    /// The proof as `Gamma || c || s`
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; VRF_PROOF_LEN] {
+        let mut bytes = [0u8; VRF_PROOF_LEN];
+        bytes[..32].copy_from_slice(&self.gamma);
+        bytes[32..32 + C_LEN].copy_from_slice(&self.c);
+        bytes[32 + C_LEN..].copy_from_slice(&self.s);
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for VrfProof {
+    type Error = SerialiseError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != VRF_PROOF_LEN {
+            return Err(SerialiseError::new(format!(
+                "VRF proof must be {VRF_PROOF_LEN} bytes, found {}",
+                bytes.len()
+            )));
+        }
+        let mut proof = Self {
+            gamma: [0; 32],
+            c: [0; C_LEN],
+            s: [0; 32],
+        };
+        proof.gamma.copy_from_slice(&bytes[..32]);
+        proof.c.copy_from_slice(&bytes[32..32 + C_LEN]);
+        proof.s.copy_from_slice(&bytes[32 + C_LEN..]);
+        Ok(proof)
+    }
+}
+
+impl RleEncodeField for VrfProof {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_data(Rc::new(ByteVec::new(self.to_bytes().to_vec().into())));
+        Ok(())
+    }
+}
+
+impl RleDecodeField for VrfProof {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        Self::try_from(fields.next_typed(FieldType::Bytes, name)?)
+    }
+}
+
+/// Try-and-increment hash of `alpha`, salted with the public key, onto the
+/// prime-order subgroup
+fn encode_to_curve(public_key: &[u8; 32], alpha: &[u8]) -> Option<EdwardsPoint> {
+    (0..=u8::MAX).find_map(|counter| {
+        let hash = Sha512::new()
+            .chain_update([SUITE, 0x01])
+            .chain_update(public_key)
+            .chain_update(alpha)
+            .chain_update([counter, 0x00])
+            .finalize();
+        let mut candidate = [0u8; 32];
+        candidate.copy_from_slice(&hash[..32]);
+        CompressedEdwardsY(candidate)
+            .decompress()
+            .map(|point| point.mul_by_cofactor())
+    })
+}
+
+fn challenge(points: &[&EdwardsPoint]) -> Scalar {
+    let mut hasher = Sha512::new().chain_update([SUITE, 0x02]);
+    for point in points {
+        hasher.update(point.compress().as_bytes());
+    }
+    let hash = hasher.chain_update([0x00]).finalize();
+    let mut bytes = [0u8; 32];
+    bytes[..C_LEN].copy_from_slice(&hash[..C_LEN]);
+    Scalar::from_bytes_mod_order(bytes)
+}
+
+fn output(gamma: &EdwardsPoint) -> [u8; VRF_OUTPUT_LEN] {
+    Sha512::new()
+        .chain_update([SUITE, 0x03])
+        .chain_update(gamma.mul_by_cofactor().compress().as_bytes())
+        .chain_update([0x00])
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{hex, unhex};
+
+    fn signer(secret: &str) -> Ed25519Signer {
+        let secret: [u8; 32] = unhex(secret).try_into().unwrap_or_else(|_| unreachable!());
+        Ed25519Signer::new(ed25519_dalek::SigningKey::from_bytes(&secret))
+    }
+
+    fn address(signer: &Ed25519Signer) -> PublicAddress {
+        PublicAddress::try_from(signer).unwrap_or_else(|_| unreachable!())
+    }
+
+    #[test]
+    fn test_rfc_9381_vectors() {
+        // RFC 9381 appendix B.3, examples 16 to 18
+        let vectors = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "",
+                "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805",
+                "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "72",
+                "f3141cd382dc42909d19ec5110469e4feae18300e94f304590abdced48aed5933bf0864a62558b3ed7f2fea45c92a465301b3bbf5e3e54ddf2d935be3b67926da3ef39226bbc355bdc9850112c8f4b02",
+                "eb4440665d3891d668e7e0fcaf587f1b4bd7fbfe99d0eb2211ccec90496310eb5e33821bc613efb94db5e5b54c70a848a0bef4553a41befc57663b56373a5031",
+            ),
+            (
+                "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+                "af82",
+                "9bc0f79119cc5604bf02d23b4caede71393cedfbb191434dd016d30177ccbf8096bb474e53895c362d8628ee9f9ea3c0e52c7a5c691b6c18c9979866568add7a2d41b00b05081ed0f58ee5e31b3a970e",
+                "645427e5d00c62a23fb703732fa5d892940935942101e456ecca7bb217c61c452118fec1219202a0edcf038bb6373241578be7217ba85a2687f7a0310b2df19f",
+            ),
+        ];
+        for (secret, alpha, pi, beta) in vectors {
+            let signer = signer(secret);
+            let alpha = unhex(alpha);
+            let proof = VrfProof::prove(&signer, &alpha).unwrap_or_else(|| unreachable!());
+            assert_eq!(hex(&proof.to_bytes()), pi);
+            let output = proof
+                .verify(&address(&signer), &alpha)
+                .unwrap_or_else(|| panic!("proof for {secret} does not verify"));
+            assert_eq!(hex(&output), beta);
+            assert_eq!(proof.output(), Some(output));
+        }
+    }
+
+    #[test]
+    fn test_rejects_wrong_key_input_and_proof() {
+        let signer = Ed25519Signer::new_random();
+        let proof = VrfProof::prove(&signer, b"slot 7").unwrap_or_else(|| unreachable!());
+        assert!(proof.verify(&address(&signer), b"slot 7").is_some());
+        assert!(proof.verify(&address(&signer), b"slot 8").is_none());
+        assert!(proof
+            .verify(&address(&Ed25519Signer::new_random()), b"slot 7")
+            .is_none());
+
+        let mut bytes = proof.to_bytes();
+        bytes[40] ^= 1;
+        let tampered = VrfProof::try_from(&bytes[..]).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert!(tampered.verify(&address(&signer), b"slot 7").is_none());
+        assert!(VrfProof::try_from(&bytes[1..]).is_err());
+
+        // the same key and input always give the same proof
+        assert_eq!(VrfProof::prove(&signer, b"slot 7"), Some(proof));
+    }
+}
@@ -0,0 +1,610 @@
+use base_xx::{byte_vec::Encodable, ByteVec, SerialiseError};
+use chrono::{DateTime, Timelike, Utc};
+use simple_sign::{Signature, SignatureError, Signer};
+use slahasher::{Hash, HashAlgorithm, Hashable};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::address::public_address::PublicAddress;
+use crate::consensus::VrfProof;
+use crate::game::BlockError;
+use crate::merkle::MerkleTree;
+use crate::serialise::{
+    FieldType, RLEByteVec, RLEFieldIter, RleDecode, RleDecodeField, RleEncode, RleEncodeField,
+};
+use crate::transactions::{verify_batch, SignedTransaction, Transaction};
+
+/// Encoding written by `Block::new`
+pub const BLOCK_VERSION: u64 = 1;
+
+/// Most transactions a block may hold
+pub const MAX_BLOCK_TRANSACTIONS: usize = 4096;
+
+/// Largest encoded block, in bytes
+pub const MAX_BLOCK_SIZE: usize = 1 << 20;
+
+/// block in a chain
+///
+/// Encodes as RLE fields `[version][time][previous_block_hash][root_hash][proposer][ticket]`,
+/// the header, followed by `[transactions][signature]`. The block hash is the
+/// KECCAK512 hash of the header and is what the proposer signs; the root hash
+/// ties the transactions to it. The ticket is the proposer's VRF proof over
+/// the slot seed, its entry in the slot lottery, see `consensus`. The
+/// genesis block has no proposer, ticket or signature, and a block has no
+/// signature until `sign` is called.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Block {
+    version: u64,
+    /// Unix timestamp in seconds
+    time: DateTime<Utc>,
+    previous_block_hash: Arc<Hash>,
+    /// Merkle root of the transaction ids
+    root_hash: Arc<Hash>,
+    proposer: Option<Rc<PublicAddress>>,
+    ticket: Option<VrfProof>,
+    transactions: Vec<SignedTransaction>,
+    signature: Option<Arc<Signature>>,
+}
+
+impl Block {
+    /// Create an unsigned block of `transactions` proposed by `proposer`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are more than `MAX_BLOCK_TRANSACTIONS`
+    /// transactions or one cannot be encoded or hashed
+    pub fn new(
+        time: DateTime<Utc>,
+        previous_block_hash: Arc<Hash>,
+        proposer: Rc<PublicAddress>,
+        transactions: Vec<SignedTransaction>,
+    ) -> Result<Self, BlockError> {
+        if transactions.len() > MAX_BLOCK_TRANSACTIONS {
+            return Err(BlockError::TooManyTransactions(transactions.len()));
+        }
+        Ok(Self {
+            version: BLOCK_VERSION,
+            time: time.with_nanosecond(0).unwrap_or(time),
+            previous_block_hash,
+            root_hash: Self::compute_root(&transactions)?,
+            proposer: Some(proposer),
+            ticket: None,
+            transactions,
+            signature: None,
+        })
+    }
+
+    /// Genesis block of a network starting at `time`
+    ///
+    /// `spec_hash`, the hash of the network's genesis specification, stands in
+    /// for the previous block hash, so networks with different specifications
+    /// never share a genesis block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the empty Merkle root cannot be hashed
+    pub fn genesis(time: DateTime<Utc>, spec_hash: Arc<Hash>) -> Result<Self, SerialiseError> {
+        Ok(Self {
+            version: BLOCK_VERSION,
+            time: time.with_nanosecond(0).unwrap_or(time),
+            previous_block_hash: spec_hash,
+            root_hash: MerkleTree::new(&[])?.root()?,
+            proposer: None,
+            ticket: None,
+            transactions: Vec::new(),
+            signature: None,
+        })
+    }
+
+    /// Set the proposer's lottery ticket, before signing
+    #[must_use]
+    pub const fn with_ticket(mut self, ticket: VrfProof) -> Self {
+        self.ticket = Some(ticket);
+        self
+    }
+
+    fn compute_root(transactions: &[SignedTransaction]) -> Result<Arc<Hash>, SerialiseError> {
+        let ids = transactions
+            .iter()
+            .map(|signed| signed.get_transaction().id())
+            .collect::<Result<Vec<_>, _>>()?;
+        MerkleTree::new(&ids)?.root()
+    }
+
+    /// Get encoding version
+    #[must_use]
+    pub const fn get_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Get block time
+    #[must_use]
+    pub const fn get_time(&self) -> &DateTime<Utc> {
+        &self.time
+    }
+
+    /// Get Merkle root of the transaction ids
+    #[must_use]
+    pub const fn get_root_hash(&self) -> &Arc<Hash> {
+        &self.root_hash
+    }
+
+    /// Get hash of the block this one follows
+    #[must_use]
+    pub const fn get_previous_block_hash(&self) -> &Arc<Hash> {
+        &self.previous_block_hash
+    }
+
+    /// Get the proposer, `None` for the genesis block
+    #[must_use]
+    pub const fn get_proposer(&self) -> Option<&Rc<PublicAddress>> {
+        self.proposer.as_ref()
+    }
+
+    /// Get the lottery ticket, `None` if the block was not made for a slot
+    #[must_use]
+    pub const fn get_ticket(&self) -> Option<&VrfProof> {
+        self.ticket.as_ref()
+    }
+
+    /// Get the transactions
+    #[must_use]
+    pub fn get_transactions(&self) -> &[SignedTransaction] {
+        &self.transactions
+    }
+
+    /// Get the proposer's signature, `None` until signed
+    #[must_use]
+    pub const fn get_signature(&self) -> Option<&Arc<Signature>> {
+        self.signature.as_ref()
+    }
+
+    fn header_rle(&self) -> Result<RLEByteVec, SerialiseError> {
+        let mut rle = RLEByteVec::default();
+        self.version.encode_field(&mut rle)?;
+        self.time.encode_field(&mut rle)?;
+        self.previous_block_hash.encode_field(&mut rle)?;
+        self.root_hash.encode_field(&mut rle)?;
+        if let Some(proposer) = &self.proposer {
+            proposer.encode_field(&mut rle)?;
+        }
+        if let Some(ticket) = &self.ticket {
+            ticket.encode_field(&mut rle)?;
+        }
+        Ok(rle)
+    }
+
+    /// Block hash, the KECCAK512 hash of the header
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header cannot be encoded or hashed
+    pub fn hash(&self) -> Result<Arc<Hash>, SerialiseError> {
+        let bytes = ByteVec::try_from(&self.header_rle()?)?;
+        Hash::try_hash(Arc::new(bytes), HashAlgorithm::KECCAK512)
+    }
+
+    /// Sign the block hash with the given signer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the block cannot be hashed or the signer fails to sign
+    pub fn try_sign<S: Signer>(&self, signer: Arc<S>) -> Result<Arc<Signature>, SignatureError> {
+        let hash = self
+            .hash()
+            .map_err(|e| SignatureError::new(e.to_string()))?;
+        signer.sign(hash)
+    }
+
+    /// Sign the block as its proposer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails or the signed block would be larger
+    /// than `MAX_BLOCK_SIZE`, leaving the block unsigned
+    pub fn sign<S: Signer>(&mut self, signer: Arc<S>) -> Result<(), BlockError> {
+        let signature = self
+            .try_sign(signer)
+            .map_err(|e| BlockError::Serialise(e.to_string()))?;
+        self.signature = Some(signature);
+        let size = self.to_byte_vec()?.get_bytes().len();
+        if size > MAX_BLOCK_SIZE {
+            self.signature = None;
+            return Err(BlockError::TooLarge(size));
+        }
+        Ok(())
+    }
+
+    /// Check the limits, that the proposer signed the block, that the root
+    /// hash matches the transactions and that every transaction is signed by
+    /// its sender
+    ///
+    /// Balances and nonces are not checked; that is the ledger's job.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first problem found
+    pub fn verify(&self) -> Result<(), BlockError> {
+        let (Some(proposer), Some(signature)) = (&self.proposer, &self.signature) else {
+            return Err(BlockError::Unsigned);
+        };
+        if self.transactions.len() > MAX_BLOCK_TRANSACTIONS {
+            return Err(BlockError::TooManyTransactions(self.transactions.len()));
+        }
+        let size = self.to_byte_vec()?.get_bytes().len();
+        if size > MAX_BLOCK_SIZE {
+            return Err(BlockError::TooLarge(size));
+        }
+        if *Self::compute_root(&self.transactions)? != *self.root_hash {
+            return Err(BlockError::BadRoot);
+        }
+        if !proposer.verify(&*self.hash()?, signature) {
+            return Err(BlockError::BadSignature);
+        }
+        let failed = verify_batch(&self.transactions);
+        if !failed.is_empty() {
+            return Err(BlockError::BadTransactions(failed));
+        }
+        Ok(())
+    }
+
+    /// Fees collected by the signer of a block including `transactions`
+    ///
+    /// Returns `None` if the total overflows, in which case the block is invalid.
+    #[must_use]
+    pub fn collect_fees(transactions: &[Transaction]) -> Option<u64> {
+        Transaction::total_fees(transactions)
+    }
+}
+
+impl Default for Block {
+    /// Create the "Genesis" block :D
+    ///
+    /// Shared by every unconfigured network; real networks start from
+    /// `GenesisSpec::genesis_block`.
+    fn default() -> Self {
+        let time = DateTime::default();
+
+        let time_millis = time.timestamp_millis();
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&time_millis.to_be_bytes());
+        let bytes = Arc::new(ByteVec::new(bytes.into()));
+
+        let root_hash = Hash::try_hash(Arc::clone(&bytes), HashAlgorithm::KECCAK512)
+            .unwrap_or_else(|_| {
+                Arc::new(Hash::new(
+                    HashAlgorithm::KECCAK512,
+                    ByteVec::new(vec![].into()),
+                ))
+            });
+        let previous_block_hash = Hash::try_hash(
+            root_hash
+                .try_to_byte_vec()
+                .unwrap_or_else(|_| Arc::new(ByteVec::new(vec![].into()))),
+            HashAlgorithm::KECCAK512,
+        )
+        .unwrap_or_else(|_| {
+            Arc::new(Hash::new(
+                HashAlgorithm::KECCAK512,
+                ByteVec::new(vec![].into()),
+            ))
+        });
+
+        Self {
+            version: BLOCK_VERSION,
+            time,
+            previous_block_hash,
+            root_hash,
+            proposer: None,
+            ticket: None,
+            transactions: Vec::new(),
+            signature: None,
+        }
+    }
+}
+
+impl RleEncode for Block {
+    fn to_rle(&self) -> Result<RLEByteVec, SerialiseError> {
+        let mut rle = self.header_rle()?;
+        self.transactions.encode_field(&mut rle)?;
+        if let Some(signature) = &self.signature {
+            signature.encode_field(&mut rle)?;
+        }
+        Ok(rle)
+    }
+}
+
+impl RleDecode for Block {
+    fn from_fields(fields: &mut RLEFieldIter<'_>) -> Result<Self, SerialiseError> {
+        let version = u64::decode_field(fields, "version")?;
+        if version != BLOCK_VERSION {
+            return Err(SerialiseError::new(format!(
+                "Unknown block version {version}"
+            )));
+        }
+        let time = DateTime::<Utc>::decode_field(fields, "time")?;
+        let previous_block_hash = Arc::<Hash>::decode_field(fields, "previous_block_hash")?;
+        let root_hash = Arc::<Hash>::decode_field(fields, "root_hash")?;
+        let proposer = if fields.peek_type() == Some(FieldType::Bytes) {
+            Some(Rc::<PublicAddress>::decode_field(fields, "proposer")?)
+        } else {
+            None
+        };
+        // only a proposer can hold a ticket
+        let ticket = if proposer.is_some() && fields.peek_type() == Some(FieldType::Bytes) {
+            Some(VrfProof::decode_field(fields, "ticket")?)
+        } else {
+            None
+        };
+        let transactions = Vec::<SignedTransaction>::decode_field(fields, "transactions")?;
+        if transactions.len() > MAX_BLOCK_TRANSACTIONS {
+            return Err(SerialiseError::new(format!(
+                "Block holds {} transactions, more than {MAX_BLOCK_TRANSACTIONS}",
+                transactions.len()
+            )));
+        }
+        let signature = if fields.peek_type() == Some(FieldType::Signature) {
+            Some(Arc::<Signature>::decode_field(fields, "signature")?)
+        } else {
+            None
+        };
+        Ok(Self {
+            version,
+            time,
+            previous_block_hash,
+            root_hash,
+            proposer,
+            ticket,
+            transactions,
+            signature,
+        })
+    }
+}
+
+impl TryFrom<&Block> for ByteVec {
+    type Error = SerialiseError;
+
+    fn try_from(value: &Block) -> Result<Self, SerialiseError> {
+        value.to_byte_vec()
+    }
+}
+
+impl base_xx::byte_vec::TryIntoByteVec for Block {
+    fn try_into_byte_vec(value: Arc<Self>) -> Result<Arc<ByteVec>, SerialiseError> {
+        Ok(Arc::new(ByteVec::try_from(value.as_ref())?))
+    }
+}
+
+impl TryFrom<ByteVec> for Block {
+    type Error = SerialiseError;
+
+    fn try_from(value: ByteVec) -> Result<Self, Self::Error> {
+        let size = value.get_bytes().len();
+        if size > MAX_BLOCK_SIZE {
+            return Err(SerialiseError::new(format!(
+                "Block of {size} bytes exceeds limit of {MAX_BLOCK_SIZE} bytes"
+            )));
+        }
+        Self::from_bytes_canonical(value.get_bytes())
+    }
+}
+
+impl Hashable for Block {}
+impl Encodable for Block {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simple_sign::Ed25519Signer;
+    use slogger::debug;
+
+    #[test]
+    fn test_play() {
+        let private_key = Arc::new(Ed25519Signer::new_random());
+
+        let block = Block::default();
+
+        let signature = block.try_sign(Arc::clone(&private_key));
+        let signature = match &signature {
+            Ok(signature) => signature,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                &Signature::default()
+            }
+        };
+
+        debug!("signature {signature:?}");
+        debug!("block {block:?}");
+
+        let private_key2 = Arc::new(Ed25519Signer::new_random());
+
+        let signature2 = block.try_sign(Arc::clone(&private_key2));
+        let signature2 = match &signature2 {
+            Ok(signature) => signature,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                &Signature::default()
+            }
+        };
+
+        debug!("signature {signature2:?}");
+
+        let winner = signature.gt(signature2);
+        debug!("winner {winner:?}");
+    }
+
+    fn address(signer: &Ed25519Signer) -> Rc<PublicAddress> {
+        Rc::new(PublicAddress::try_from(signer).unwrap_or_else(|_| unreachable!()))
+    }
+
+    fn payments(signer: &Arc<Ed25519Signer>, count: u64) -> Vec<SignedTransaction> {
+        let from = address(signer);
+        (0..count)
+            .map(|nonce| {
+                let transaction =
+                    Transaction::new(Rc::clone(&from), Rc::clone(&from), 1, Utc::now(), nonce);
+                SignedTransaction::new(transaction, Arc::clone(signer))
+                    .unwrap_or_else(|e| panic!("sign: {e}"))
+            })
+            .collect()
+    }
+
+    fn signed_block(proposer: &Arc<Ed25519Signer>, transactions: Vec<SignedTransaction>) -> Block {
+        let genesis = Block::default();
+        let previous = genesis.hash().unwrap_or_else(|e| panic!("hash: {e}"));
+        let mut block = Block::new(Utc::now(), previous, address(proposer), transactions)
+            .unwrap_or_else(|e| panic!("block: {e}"));
+        block
+            .sign(Arc::clone(proposer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        block
+    }
+
+    #[test]
+    fn test_collect_fees() {
+        let from = address(&Ed25519Signer::new_random());
+        let transactions: Vec<_> = (0..3)
+            .map(|nonce| {
+                Transaction::new(Rc::clone(&from), Rc::clone(&from), 1, Utc::now(), nonce)
+                    .with_fee(nonce + 1)
+            })
+            .collect();
+        assert_eq!(Block::collect_fees(&transactions), Some(6));
+        assert_eq!(Block::collect_fees(&[]), Some(0));
+    }
+
+    #[test]
+    fn test_transaction_inclusion() {
+        let sender = Arc::new(Ed25519Signer::new_random());
+        let block = signed_block(&Arc::new(Ed25519Signer::new_random()), payments(&sender, 5));
+
+        let ids: Vec<_> = block
+            .get_transactions()
+            .iter()
+            .map(|signed| {
+                signed
+                    .get_transaction()
+                    .id()
+                    .unwrap_or_else(|e| panic!("id: {e}"))
+            })
+            .collect();
+        let proof = MerkleTree::new(&ids)
+            .unwrap_or_else(|e| panic!("tree: {e}"))
+            .proof(3)
+            .unwrap_or_else(|| unreachable!());
+        assert!(proof.verify(&ids[3], block.get_root_hash()));
+        assert!(!proof.verify(&ids[2], block.get_root_hash()));
+    }
+
+    #[test]
+    fn test_block_roundtrip() {
+        let proposer = Arc::new(Ed25519Signer::new_random());
+        let sender = Arc::new(Ed25519Signer::new_random());
+        let block = signed_block(&proposer, payments(&sender, 3));
+        assert_eq!(block.verify(), Ok(()));
+
+        let bytes = ByteVec::try_from(&block).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = Block::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded, block);
+        assert_eq!(decoded.verify(), Ok(()));
+        assert_eq!(decoded.hash().ok(), block.hash().ok());
+
+        // genesis has no proposer or signature
+        let genesis = Block::default();
+        let bytes = ByteVec::try_from(&genesis).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = Block::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded, genesis);
+        assert_eq!(decoded.verify(), Err(BlockError::Unsigned));
+    }
+
+    #[test]
+    fn test_block_verify_failures() {
+        let proposer = Arc::new(Ed25519Signer::new_random());
+        let sender = Arc::new(Ed25519Signer::new_random());
+
+        // signed by someone other than the proposer
+        let mut block = signed_block(&proposer, payments(&sender, 2));
+        block
+            .sign(Arc::new(Ed25519Signer::new_random()))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(block.verify(), Err(BlockError::BadSignature));
+
+        // a transaction dropped after signing
+        let mut block = signed_block(&proposer, payments(&sender, 2));
+        block.transactions.pop();
+        assert_eq!(block.verify(), Err(BlockError::BadRoot));
+
+        // a transaction signed by someone other than its sender
+        let mut transactions = payments(&sender, 2);
+        let forged = payments(&Arc::new(Ed25519Signer::new_random()), 1).remove(0);
+        let transaction = Transaction::new(
+            Rc::clone(transactions[0].get_transaction().get_from()),
+            Rc::clone(transactions[0].get_transaction().get_to()),
+            1,
+            Utc::now(),
+            2,
+        );
+        let id = transaction.id().unwrap_or_else(|e| panic!("id: {e}"));
+        transactions.push(SignedTransaction::from_parts(
+            transaction,
+            crate::transactions::TransactionSignature::from_parts(
+                id,
+                Arc::clone(forged.get_signature().get_signature()),
+            ),
+        ));
+        let block = signed_block(&proposer, transactions);
+        assert_eq!(block.verify(), Err(BlockError::BadTransactions(vec![2])));
+    }
+
+    #[test]
+    fn test_block_size_limits() {
+        let proposer = address(&Ed25519Signer::new_random());
+        let from = address(&Ed25519Signer::new_random());
+        let memo = "x".repeat(crate::transactions::transaction::MAX_MEMO_LEN);
+        let unchecked = |nonce| {
+            let transaction =
+                Transaction::new(Rc::clone(&from), Rc::clone(&from), 1, Utc::now(), nonce)
+                    .with_memo(memo.clone())
+                    .unwrap_or_else(|e| panic!("memo: {e}"));
+            let id = transaction.id().unwrap_or_else(|e| panic!("id: {e}"));
+            SignedTransaction::from_parts(
+                transaction,
+                crate::transactions::TransactionSignature::from_parts(
+                    id,
+                    Arc::new(Signature::default()),
+                ),
+            )
+        };
+        let previous = Block::default()
+            .hash()
+            .unwrap_or_else(|e| panic!("hash: {e}"));
+
+        let too_many: Vec<_> = (0..=MAX_BLOCK_TRANSACTIONS as u64).map(unchecked).collect();
+        assert_eq!(
+            Block::new(
+                Utc::now(),
+                Arc::clone(&previous),
+                Rc::clone(&proposer),
+                too_many
+            )
+            .err(),
+            Some(BlockError::TooManyTransactions(MAX_BLOCK_TRANSACTIONS + 1))
+        );
+
+        let bulky: Vec<_> = (0..MAX_BLOCK_TRANSACTIONS as u64).map(unchecked).collect();
+        let mut block = Block::new(Utc::now(), previous, proposer, bulky)
+            .unwrap_or_else(|e| panic!("block: {e}"));
+        assert!(matches!(
+            block.sign(Arc::new(Ed25519Signer::new_random())),
+            Err(BlockError::TooLarge(_))
+        ));
+        assert_eq!(block.get_signature(), None);
+
+        let oversized = block
+            .to_byte_vec()
+            .unwrap_or_else(|e| panic!("encode: {e}"));
+        assert!(oversized.get_bytes().len() > MAX_BLOCK_SIZE);
+        assert!(Block::try_from(oversized).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+/// Why a block was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    /// More transactions than `MAX_BLOCK_TRANSACTIONS`
+    TooManyTransactions(usize),
+    /// Encoded size in bytes above `MAX_BLOCK_SIZE`
+    TooLarge(usize),
+    /// The block has no proposer or no signature
+    Unsigned,
+    /// The signature is not the proposer's over the block hash
+    BadSignature,
+    /// The root hash is not the Merkle root of the transactions
+    BadRoot,
+    /// Indexes of transactions whose signatures do not verify
+    BadTransactions(Vec<usize>),
+    /// The block could not be encoded or hashed
+    Serialise(String),
+}
+
+impl Display for BlockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooManyTransactions(count) => {
+                write!(f, "Block holds {count} transactions, too many")
+            }
+            Self::TooLarge(size) => write!(f, "Block of {size} bytes is too large"),
+            Self::Unsigned => write!(f, "Block is not signed"),
+            Self::BadSignature => write!(f, "Block signature is not the proposer's"),
+            Self::BadRoot => write!(f, "Root hash does not match the transactions"),
+            Self::BadTransactions(indexes) => {
+                write!(f, "Transactions {indexes:?} have invalid signatures")
+            }
+            Self::Serialise(reason) => write!(f, "Block could not be encoded: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+impl From<base_xx::SerialiseError> for BlockError {
+    fn from(value: base_xx::SerialiseError) -> Self {
+        Self::Serialise(value.to_string())
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt::Display;
+
+use crate::chain::ChainError;
+use crate::keystore::KeystoreError;
+use crate::ledger::LedgerError;
+
+/// Why a genesis specification could not be loaded or used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenesisError {
+    /// The network name is not a known preset
+    UnknownNetwork(String),
+    /// The genesis file is for a different network than configured
+    NetworkMismatch {
+        /// Configured network
+        expected: String,
+        /// Network named in the file
+        found: String,
+    },
+    /// The network has no usable preset and no genesis file is configured
+    MissingGenesisFile(String),
+    /// The initial balances cannot be credited
+    Ledger(LedgerError),
+    /// A preset key could not be derived
+    Keystore(KeystoreError),
+    /// The genesis block cannot start a chain
+    Chain(ChainError),
+    /// The genesis file could not be read or written
+    Io(String),
+    /// The specification could not be encoded, decoded or hashed
+    Serialise(String),
+}
+
+impl Display for GenesisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownNetwork(name) => write!(f, "Unknown network {name}"),
+            Self::NetworkMismatch { expected, found } => {
+                write!(f, "Genesis file is for {found}, configured for {expected}")
+            }
+            Self::MissingGenesisFile(network) => {
+                write!(f, "A genesis file must be configured for {network}")
+            }
+            Self::Ledger(e) => write!(f, "Invalid initial balances: {e}"),
+            Self::Keystore(e) => write!(f, "Preset key error: {e}"),
+            Self::Chain(e) => write!(f, "Invalid genesis block: {e}"),
+            Self::Io(e) => write!(f, "Genesis file error: {e}"),
+            Self::Serialise(reason) => write!(f, "Genesis could not be encoded: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for GenesisError {}
+
+impl From<LedgerError> for GenesisError {
+    fn from(value: LedgerError) -> Self {
+        Self::Ledger(value)
+    }
+}
+
+impl From<KeystoreError> for GenesisError {
+    fn from(value: KeystoreError) -> Self {
+        Self::Keystore(value)
+    }
+}
+
+impl From<ChainError> for GenesisError {
+    fn from(value: ChainError) -> Self {
+        Self::Chain(value)
+    }
+}
+
+impl From<std::io::Error> for GenesisError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value.to_string())
+    }
+}
+
+impl From<base_xx::SerialiseError> for GenesisError {
+    fn from(value: base_xx::SerialiseError) -> Self {
+        Self::Serialise(value.to_string())
+    }
+}
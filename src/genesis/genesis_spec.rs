@@ -0,0 +1,357 @@
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use base_xx::SerialiseError;
+use chrono::{DateTime, Timelike, Utc};
+use slahasher::{Hash, HashAlgorithm};
+
+use crate::address::public_address::PublicAddress;
+use crate::chain::Chain;
+use crate::config::CONFIG;
+use crate::consensus::Consensus;
+use crate::game::Block;
+use crate::genesis::GenesisError;
+use crate::ledger::Ledger;
+use crate::serialise::{RleDecode, RleEncode};
+
+/// Coins credited to an address at genesis
+#[derive(Debug, Clone, PartialEq, Eq, RleEncode, RleDecode)]
+pub struct GenesisBalance {
+    /// Address credited
+    address: Rc<PublicAddress>,
+
+    /// Amount credited
+    amount: u64,
+}
+
+impl GenesisBalance {
+    /// Credit `amount` to `address`
+    #[must_use]
+    pub const fn new(address: Rc<PublicAddress>, amount: u64) -> Self {
+        Self { address, amount }
+    }
+
+    /// Get the address credited
+    #[must_use]
+    pub const fn get_address(&self) -> &Rc<PublicAddress> {
+        &self.address
+    }
+
+    /// Get the amount credited
+    #[must_use]
+    pub const fn get_amount(&self) -> u64 {
+        self.amount
+    }
+}
+
+/// Everything a network agrees on before its first block
+///
+/// Encodes as RLE fields `[network][start_time][balances][moderators]`. The
+/// KECCAK512 hash of the encoding is committed to by the genesis block, so the
+/// genesis block hash identifies the network: nodes with any difference in
+/// their specification build incompatible chains.
+#[derive(Debug, Clone, PartialEq, Eq, RleEncode, RleDecode)]
+pub struct GenesisSpec {
+    /// Name of the network, such as `mainnet`
+    network: String,
+
+    /// Time of the genesis block, in whole seconds
+    start_time: DateTime<Utc>,
+
+    /// Initial balances
+    balances: Vec<GenesisBalance>,
+
+    /// Signers allowed to propose blocks
+    moderators: Vec<Rc<PublicAddress>>,
+}
+
+impl GenesisSpec {
+    /// Specification of `network` starting at `start_time`, with no balances
+    /// or moderators
+    #[must_use]
+    pub fn new(network: &str, start_time: DateTime<Utc>) -> Self {
+        Self {
+            network: network.to_string(),
+            start_time: start_time.with_nanosecond(0).unwrap_or(start_time),
+            balances: Vec::new(),
+            moderators: Vec::new(),
+        }
+    }
+
+    /// Credit `amount` to `address` at genesis
+    #[must_use]
+    pub fn with_balance(mut self, address: Rc<PublicAddress>, amount: u64) -> Self {
+        self.balances.push(GenesisBalance::new(address, amount));
+        self
+    }
+
+    /// Allow `address` to propose blocks
+    #[must_use]
+    pub fn with_moderator(mut self, address: Rc<PublicAddress>) -> Self {
+        self.moderators.push(address);
+        self
+    }
+
+    /// Get the network name
+    #[must_use]
+    pub const fn get_network(&self) -> &String {
+        &self.network
+    }
+
+    /// Get the time of the genesis block
+    #[must_use]
+    pub const fn get_start_time(&self) -> &DateTime<Utc> {
+        &self.start_time
+    }
+
+    /// Get the initial balances
+    #[must_use]
+    pub fn get_balances(&self) -> &[GenesisBalance] {
+        &self.balances
+    }
+
+    /// Get the initial moderators
+    #[must_use]
+    pub fn get_moderators(&self) -> &[Rc<PublicAddress>] {
+        &self.moderators
+    }
+
+    /// KECCAK512 hash of the encoded specification
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification cannot be encoded or hashed
+    pub fn hash(&self) -> Result<Arc<Hash>, SerialiseError> {
+        Hash::try_hash(Arc::new(self.to_byte_vec()?), HashAlgorithm::KECCAK512)
+    }
+
+    /// First block of the network, committing to this specification
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification cannot be hashed
+    pub fn genesis_block(&self) -> Result<Block, SerialiseError> {
+        Block::genesis(self.start_time, self.hash()?)
+    }
+
+    /// Network id, the hash of the genesis block
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the genesis block cannot be hashed
+    pub fn network_id(&self) -> Result<Arc<Hash>, SerialiseError> {
+        self.genesis_block()?.hash()
+    }
+
+    /// Ledger holding the initial balances
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the balances of an address overflow
+    pub fn ledger(&self) -> Result<Ledger, GenesisError> {
+        let mut ledger = Ledger::new();
+        for balance in &self.balances {
+            ledger.credit(&balance.address, balance.amount)?;
+        }
+        Ok(ledger)
+    }
+
+    /// Consensus among the initial moderators
+    #[must_use]
+    pub fn consensus(&self) -> Consensus {
+        Consensus::new(self.moderators.iter().map(Rc::clone))
+    }
+
+    /// Chain holding only the genesis block
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the genesis block cannot be built
+    pub fn chain(&self) -> Result<Chain, GenesisError> {
+        Ok(Chain::new(self.genesis_block()?, self.consensus())?)
+    }
+
+    /// Write the specification to `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the specification cannot be encoded or written
+    pub fn save(&self, path: &Path) -> Result<(), GenesisError> {
+        fs::write(path, self.to_byte_vec()?.get_bytes())?;
+        Ok(())
+    }
+
+    /// Read a specification written by [`GenesisSpec::save`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a canonical
+    /// encoding
+    pub fn load(path: &Path) -> Result<Self, GenesisError> {
+        Ok(Self::from_bytes_canonical(&fs::read(path)?)?)
+    }
+
+    /// Specification of the configured network
+    ///
+    /// Uses the genesis file when one is configured, otherwise the network's
+    /// preset. Networks whose preset has no moderators must have a genesis
+    /// file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required genesis file is not configured, or the
+    /// file cannot be loaded or names a different network
+    pub fn from_config() -> Result<Self, GenesisError> {
+        let (network, path) = CONFIG.with(|config| {
+            let config = config.borrow();
+            (
+                config.get_network(),
+                config.get_genesis_path().map(str::to_string),
+            )
+        });
+        let Some(path) = path else {
+            if network.needs_genesis_file() {
+                return Err(GenesisError::MissingGenesisFile(network.to_string()));
+            }
+            return network.genesis();
+        };
+        let spec = Self::load(Path::new(&path))?;
+        if spec.network != network.get_name() {
+            return Err(GenesisError::NetworkMismatch {
+                expected: network.get_name().to_string(),
+                found: spec.network,
+            });
+        }
+        Ok(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis::Network;
+    use chrono::TimeZone;
+    use rand_core::{OsRng, RngCore};
+    use simple_sign::Ed25519Signer;
+
+    fn address() -> Rc<PublicAddress> {
+        Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        )
+    }
+
+    fn id(spec: &GenesisSpec) -> Arc<Hash> {
+        spec.network_id().unwrap_or_else(|e| panic!("id: {e}"))
+    }
+
+    fn temp_path(label: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("subversive-genesis-{label}-{}", OsRng.next_u64()))
+    }
+
+    #[test]
+    fn test_network_id_commits_to_spec() {
+        let start = Utc
+            .timestamp_opt(1_800_000_000, 0)
+            .single()
+            .unwrap_or_else(|| unreachable!());
+        let moderator = address();
+        let base = GenesisSpec::new("test", start)
+            .with_balance(Rc::clone(&moderator), 100)
+            .with_moderator(Rc::clone(&moderator));
+        assert_eq!(id(&base), id(&base.clone()));
+
+        let variants = [
+            GenesisSpec::new("other", start)
+                .with_balance(Rc::clone(&moderator), 100)
+                .with_moderator(Rc::clone(&moderator)),
+            GenesisSpec::new("test", start + chrono::TimeDelta::seconds(1))
+                .with_balance(Rc::clone(&moderator), 100)
+                .with_moderator(Rc::clone(&moderator)),
+            GenesisSpec::new("test", start)
+                .with_balance(Rc::clone(&moderator), 101)
+                .with_moderator(Rc::clone(&moderator)),
+            GenesisSpec::new("test", start)
+                .with_balance(Rc::clone(&moderator), 100)
+                .with_moderator(address()),
+        ];
+        for variant in &variants {
+            assert_ne!(id(variant), id(&base));
+        }
+
+        let genesis = base
+            .genesis_block()
+            .unwrap_or_else(|e| panic!("block: {e}"));
+        assert_eq!(genesis.get_time(), &start);
+        assert_eq!(
+            genesis.get_previous_block_hash(),
+            &base.hash().unwrap_or_else(|e| panic!("hash: {e}"))
+        );
+    }
+
+    #[test]
+    fn test_presets_are_distinct() {
+        let ids: Vec<_> = [Network::Mainnet, Network::Testnet, Network::Devnet]
+            .iter()
+            .map(|network| id(&network.genesis().unwrap_or_else(|e| panic!("{e}"))))
+            .collect();
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(ids[0], ids[2]);
+        assert_ne!(ids[1], ids[2]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = temp_path("roundtrip");
+        let spec = Network::Devnet
+            .genesis()
+            .unwrap_or_else(|e| panic!("{e}"))
+            .with_balance(address(), 7);
+        spec.save(&path).unwrap_or_else(|e| panic!("save: {e}"));
+        let loaded = GenesisSpec::load(&path).unwrap_or_else(|e| panic!("load: {e}"));
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded, spec);
+        assert_eq!(id(&loaded), id(&spec));
+
+        assert!(matches!(GenesisSpec::load(&path), Err(GenesisError::Io(_))));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let path = temp_path("config");
+        let spec =
+            GenesisSpec::new(Network::Testnet.get_name(), Utc::now()).with_moderator(address());
+        spec.save(&path).unwrap_or_else(|e| panic!("save: {e}"));
+
+        let from_config = |network: Network, path: Option<&Path>| {
+            CONFIG.with(|config| {
+                let mut config = config.borrow_mut();
+                config.set_network(network);
+                config.set_genesis_path(path.map(|path| path.to_string_lossy().to_string()));
+            });
+            GenesisSpec::from_config()
+        };
+
+        assert_eq!(
+            from_config(Network::Devnet, None),
+            Network::Devnet.genesis()
+        );
+        for network in [Network::Mainnet, Network::Testnet] {
+            assert_eq!(
+                from_config(network, None),
+                Err(GenesisError::MissingGenesisFile(network.to_string()))
+            );
+        }
+        assert_eq!(from_config(Network::Testnet, Some(&path)), Ok(spec));
+        assert_eq!(
+            from_config(Network::Mainnet, Some(&path)),
+            Err(GenesisError::NetworkMismatch {
+                expected: "mainnet".to_string(),
+                found: "testnet".to_string()
+            })
+        );
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,12 @@
+/// Genesis errors
+pub mod genesis_error;
+
+/// Network genesis specifications
+pub mod genesis_spec;
+
+/// Preset networks
+pub mod network;
+
+pub use genesis_error::GenesisError;
+pub use genesis_spec::{GenesisBalance, GenesisSpec};
+pub use network::Network;
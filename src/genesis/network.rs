@@ -0,0 +1,174 @@
+use std::fmt::Display;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use chrono::{DateTime, TimeZone, Utc};
+use simple_sign::Ed25519Signer;
+
+use crate::address::public_address::PublicAddress;
+use crate::genesis::{GenesisError, GenesisSpec};
+use crate::keystore::{KeystoreError, Mnemonic};
+
+/// Start of mainnet, 2027-01-01T00:00:00Z
+const MAINNET_START: i64 = 1_798_761_600;
+
+/// Start of testnet, 2026-11-01T00:00:00Z
+const TESTNET_START: i64 = 1_793_491_200;
+
+/// Start of devnet, 2026-01-01T00:00:00Z
+const DEVNET_START: i64 = 1_767_225_600;
+
+/// Well-known phrase of the devnet moderator, the BIP39 test vector
+const DEVNET_PHRASE: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+/// Coins credited to the devnet moderator
+const DEVNET_BALANCE: u64 = 1_000_000_000;
+
+/// Networks with a preset genesis specification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Network {
+    /// The public network
+    Mainnet,
+    /// Public network for testing releases
+    Testnet,
+    /// Local network whose moderator key is public
+    #[default]
+    Devnet,
+}
+
+impl Network {
+    /// Get the network name
+    #[must_use]
+    pub const fn get_name(&self) -> &'static str {
+        match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+            Self::Devnet => "devnet",
+        }
+    }
+
+    /// Whether the network's balances and moderators come from a genesis file
+    ///
+    /// True for mainnet and testnet, whose presets alone have no moderators
+    /// and so could never add a block.
+    #[must_use]
+    pub const fn needs_genesis_file(&self) -> bool {
+        !matches!(self, Self::Devnet)
+    }
+
+    /// Preset genesis specification
+    ///
+    /// Mainnet and testnet presets fix only the name and start time; their
+    /// balances and moderators are distributed as a genesis file, see
+    /// [`Network::needs_genesis_file`]. Devnet has one moderator holding every
+    /// coin, whose key is [`Network::devnet_signer`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the devnet moderator cannot be derived
+    pub fn genesis(&self) -> Result<GenesisSpec, GenesisError> {
+        let spec = GenesisSpec::new(self.get_name(), Self::at(self.start()));
+        if self.needs_genesis_file() {
+            return Ok(spec);
+        }
+        let moderator = Rc::new(
+            PublicAddress::try_from(&Self::devnet_signer()?)
+                .map_err(|e| GenesisError::Serialise(e.to_string()))?,
+        );
+        Ok(spec
+            .with_balance(Rc::clone(&moderator), DEVNET_BALANCE)
+            .with_moderator(moderator))
+    }
+
+    /// Signer of the devnet moderator, identity 0 of the BIP39 test phrase
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the identity cannot be derived
+    pub fn devnet_signer() -> Result<Ed25519Signer, KeystoreError> {
+        Mnemonic::from_str(DEVNET_PHRASE)?.derive_identity("", 0)
+    }
+
+    const fn start(self) -> i64 {
+        match self {
+            Self::Mainnet => MAINNET_START,
+            Self::Testnet => TESTNET_START,
+            Self::Devnet => DEVNET_START,
+        }
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(seconds, 0).single().unwrap_or_default()
+    }
+}
+
+impl FromStr for Network {
+    type Err = GenesisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(Self::Mainnet),
+            "testnet" => Ok(Self::Testnet),
+            "devnet" => Ok(Self::Devnet),
+            _ => Err(GenesisError::UnknownNetwork(s.to_string())),
+        }
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Proposer;
+    use chrono::TimeDelta;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_names() {
+        for network in [Network::Mainnet, Network::Testnet, Network::Devnet] {
+            assert_eq!(Network::from_str(&network.to_string()), Ok(network));
+        }
+        assert_eq!(
+            Network::from_str("Mainnet"),
+            Err(GenesisError::UnknownNetwork("Mainnet".to_string()))
+        );
+        assert_eq!(Network::default(), Network::Devnet);
+    }
+
+    #[test]
+    fn test_devnet() {
+        let spec = Network::Devnet
+            .genesis()
+            .unwrap_or_else(|e| panic!("genesis: {e}"));
+        let signer = Arc::new(Network::devnet_signer().unwrap_or_else(|e| panic!("signer: {e}")));
+        let moderator =
+            Rc::new(PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!()));
+        let proposer = Proposer {
+            signer,
+            address: Rc::clone(&moderator),
+        };
+        assert_eq!(spec.get_moderators(), [Rc::clone(&moderator)]);
+        assert_eq!(
+            spec.ledger()
+                .unwrap_or_else(|e| panic!("ledger: {e}"))
+                .get_balance(&moderator),
+            DEVNET_BALANCE
+        );
+        assert!(spec.consensus().is_eligible(&moderator));
+
+        let mut chain = spec.chain().unwrap_or_else(|e| panic!("chain: {e}"));
+        let genesis = Arc::clone(chain.get_tip_hash());
+        assert_eq!(Some(genesis.as_ref()), spec.network_id().ok().as_deref());
+
+        let time = spec.get_start_time().to_owned() + TimeDelta::seconds(600);
+        let block = proposer.propose(time, chain.get_tip());
+        let mut ledger = spec.ledger().unwrap_or_else(|e| panic!("ledger: {e}"));
+        assert!(chain.append(block, &mut ledger, &Utc::now()).is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+use ed25519_dalek::SigningKey;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use simple_sign::Ed25519Signer;
+
+use crate::keystore::KeystoreError;
+
+/// Key used for the master HMAC, from SLIP-0010
+const ED25519_SEED_KEY: &[u8] = b"ed25519 seed";
+
+/// Bit set on hardened child indexes
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// Path prefix for subversive identities, `m/44'/<coin type>'`
+///
+/// The account index is appended as the last hardened level, one per forum
+/// identity or device.
+pub const IDENTITY_PATH: &str = "m/44'/9393'";
+
+/// SLIP-0010 extended Ed25519 private key
+///
+/// Ed25519 only supports hardened derivation, so every path level is hardened
+/// whether or not it is written with a `'`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedKey {
+    key: [u8; 32],
+    chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    fn from_hmac(hmac_key: &[u8], data: &[&[u8]]) -> Result<Self, KeystoreError> {
+        let mut mac = Hmac::<Sha512>::new_from_slice(hmac_key)
+            .map_err(|e| KeystoreError::Corrupt(e.to_string()))?;
+        for part in data {
+            mac.update(part);
+        }
+        let out = mac.finalize().into_bytes();
+        let mut key = [0u8; 32];
+        let mut chain_code = [0u8; 32];
+        key.copy_from_slice(&out[..32]);
+        chain_code.copy_from_slice(&out[32..]);
+        Ok(Self { key, chain_code })
+    }
+
+    /// Derive the master key from a seed, usually `Mnemonic::to_seed`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HMAC cannot be keyed, which does not happen in practice
+    pub fn from_seed(seed: &[u8]) -> Result<Self, KeystoreError> {
+        Self::from_hmac(ED25519_SEED_KEY, &[seed])
+    }
+
+    /// Derive the hardened child at `index`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HMAC cannot be keyed, which does not happen in practice
+    pub fn derive_child(&self, index: u32) -> Result<Self, KeystoreError> {
+        let index = (index | HARDENED).to_be_bytes();
+        Self::from_hmac(&self.chain_code, &[&[0], &self.key, &index])
+    }
+
+    /// Derive along a path such as `m/44'/9393'/0'`
+    ///
+    /// SLIP-0010 defines only hardened derivation for Ed25519, so every level
+    /// must be marked with `'` or `H`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path does not start at `m` or has a bad or
+    /// unhardened index
+    pub fn derive_path(&self, path: &str) -> Result<Self, KeystoreError> {
+        let bad_path = || KeystoreError::BadPath(path.to_string());
+        let mut levels = path.split('/');
+        if levels.next() != Some("m") {
+            return Err(bad_path());
+        }
+        let mut key = self.clone();
+        for level in levels {
+            let index = level
+                .strip_suffix('\'')
+                .or_else(|| level.strip_suffix('H'))
+                .ok_or_else(bad_path)?;
+            let index = index.parse::<u32>().map_err(|_| bad_path())?;
+            if index >= HARDENED {
+                return Err(bad_path());
+            }
+            key = key.derive_child(index)?;
+        }
+        Ok(key)
+    }
+
+    /// Get the private key bytes
+    #[must_use]
+    pub const fn get_key(&self) -> &[u8; 32] {
+        &self.key
+    }
+
+    /// Get the chain code
+    #[must_use]
+    pub const fn get_chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// Signer for this key
+    #[must_use]
+    pub fn to_signer(&self) -> Ed25519Signer {
+        Ed25519Signer::new(SigningKey::from_bytes(&self.key))
+    }
+}
+
+impl std::fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtendedKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hex;
+
+    /// SLIP-0010 test vector 1 for ed25519
+    #[test]
+    fn test_slip10_vector() {
+        let seed: Vec<u8> = (0u8..16).collect();
+        let master = ExtendedKey::from_seed(&seed).unwrap_or_else(|e| panic!("master: {e}"));
+        assert_eq!(
+            hex(master.get_key()),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex(master.get_chain_code()),
+            "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb"
+        );
+
+        let child = master
+            .derive_path("m/0'")
+            .unwrap_or_else(|e| panic!("m/0': {e}"));
+        assert_eq!(
+            hex(child.get_key()),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        assert_eq!(
+            hex(child.get_chain_code()),
+            "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69"
+        );
+        assert_eq!(
+            hex(child.to_signer().get_verifying_key().as_bytes()),
+            "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c"
+        );
+
+        let grandchild = master
+            .derive_path("m/0H/1H")
+            .unwrap_or_else(|e| panic!("m/0'/1': {e}"));
+        assert_eq!(
+            hex(grandchild.get_key()),
+            "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2"
+        );
+    }
+
+    #[test]
+    fn test_bad_paths() {
+        let master = ExtendedKey::from_seed(&[7; 64]).unwrap_or_else(|e| panic!("master: {e}"));
+        for path in [
+            "",
+            "0'",
+            "m/x'",
+            "m/2147483648'",
+            "n/0'",
+            "m/0",
+            "m/44'/9393'/0",
+        ] {
+            assert_eq!(
+                master.derive_path(path).err(),
+                Some(KeystoreError::BadPath(path.to_string()))
+            );
+        }
+        assert_eq!(master.derive_path("m").ok(), Some(master));
+    }
+}
@@ -0,0 +1,491 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base_xx::ByteVec;
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+use rand_core::{OsRng, RngCore};
+use simple_sign::Ed25519Signer;
+use zeroize::Zeroizing;
+
+use crate::address::public_address::PublicAddress;
+use crate::keystore::KeystoreError;
+use crate::serialise::{RleDecode, RleEncode};
+
+/// File extension of stored identities
+const EXTENSION: &str = "key";
+
+/// Longest accepted identity name
+const MAX_NAME_LEN: usize = 64;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const SEED_LEN: usize = 32;
+
+/// Largest accepted Argon2 memory cost, 256 MiB in KiB, so a crafted file
+/// cannot exhaust memory on unlock
+const MAX_MEMORY_COST: u64 = 256 * 1024;
+
+/// Largest accepted Argon2 time cost
+const MAX_TIME_COST: u64 = 16;
+
+/// Largest accepted Argon2 parallelism
+const MAX_PARALLELISM: u64 = 16;
+
+/// One stored identity
+///
+/// The address is kept in the clear so it can be read without the passphrase,
+/// and is bound to the ciphertext as associated data.
+#[derive(Debug, RleEncode, RleDecode)]
+struct KeystoreEntry {
+    address: PublicAddress,
+    memory_cost: u64,
+    time_cost: u64,
+    parallelism: u64,
+    salt: ByteVec,
+    nonce: ByteVec,
+    ciphertext: ByteVec,
+}
+
+impl KeystoreEntry {
+    fn check_params(&self) -> Result<(), KeystoreError> {
+        if self.memory_cost > MAX_MEMORY_COST
+            || self.time_cost > MAX_TIME_COST
+            || self.parallelism > MAX_PARALLELISM
+        {
+            return Err(KeystoreError::Corrupt(
+                "Key derivation parameters exceed limits".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn cipher(&self, passphrase: &str) -> Result<XChaCha20Poly1305, KeystoreError> {
+        self.check_params()?;
+        let corrupt = |_| KeystoreError::Corrupt("Invalid key derivation parameters".to_string());
+        let params = Params::new(
+            u32::try_from(self.memory_cost).map_err(corrupt)?,
+            u32::try_from(self.time_cost).map_err(corrupt)?,
+            u32::try_from(self.parallelism).map_err(corrupt)?,
+            Some(SEED_LEN),
+        )
+        .map_err(|e| KeystoreError::Corrupt(e.to_string()))?;
+
+        let mut key = Zeroizing::new([0u8; SEED_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), self.salt.get_bytes(), key.as_mut())
+            .map_err(|e| KeystoreError::Corrupt(e.to_string()))?;
+        XChaCha20Poly1305::new_from_slice(key.as_ref())
+            .map_err(|e| KeystoreError::Corrupt(e.to_string()))
+    }
+
+    fn seal(
+        signer: &Ed25519Signer,
+        passphrase: &str,
+        params: &Params,
+    ) -> Result<Self, KeystoreError> {
+        let address = PublicAddress::try_from(signer)?;
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut entry = Self {
+            address,
+            memory_cost: params.m_cost().into(),
+            time_cost: params.t_cost().into(),
+            parallelism: params.p_cost().into(),
+            salt: ByteVec::new(salt.to_vec().into()),
+            nonce: ByteVec::new(nonce.to_vec().into()),
+            ciphertext: ByteVec::new(vec![].into()),
+        };
+
+        let aad = ByteVec::try_from(&entry.address)?;
+        let ciphertext = entry
+            .cipher(passphrase)?
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: signer.get_signing_key().as_bytes(),
+                    aad: aad.get_bytes(),
+                },
+            )
+            .map_err(|_| KeystoreError::Corrupt("Encryption failed".to_string()))?;
+        entry.ciphertext = ByteVec::new(ciphertext.into());
+        Ok(entry)
+    }
+
+    fn open(&self, passphrase: &str) -> Result<Ed25519Signer, KeystoreError> {
+        if self.nonce.get_bytes().len() != NONCE_LEN {
+            return Err(KeystoreError::Corrupt("Invalid nonce".to_string()));
+        }
+        let aad = ByteVec::try_from(&self.address)?;
+        let seed = self
+            .cipher(passphrase)?
+            .decrypt(
+                XNonce::from_slice(self.nonce.get_bytes()),
+                Payload {
+                    msg: self.ciphertext.get_bytes(),
+                    aad: aad.get_bytes(),
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| KeystoreError::BadPassphrase)?;
+        let seed = Zeroizing::new(
+            <[u8; SEED_LEN]>::try_from(seed.as_slice())
+                .map_err(|_| KeystoreError::Corrupt("Invalid key length".to_string()))?,
+        );
+        Ok(Ed25519Signer::new(ed25519_dalek::SigningKey::from_bytes(
+            &seed,
+        )))
+    }
+}
+
+/// Passphrase-encrypted store of named `Ed25519Signer` identities
+///
+/// Each identity is one file in the keystore directory holding its
+/// `PublicAddress` in the clear and its signing key encrypted with
+/// XChaCha20-Poly1305 under an Argon2id key derived from the passphrase.
+#[derive(Debug, Clone)]
+pub struct Keystore {
+    path: PathBuf,
+    params: Params,
+}
+
+impl Keystore {
+    /// Open the keystore in `path`, created on first save
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            params: Params::default(),
+        }
+    }
+
+    /// Open the keystore under `Config::get_db_path`
+    #[must_use]
+    pub fn from_config() -> Self {
+        let db_path = crate::config::CONFIG.with(|config| config.borrow().get_db_path().clone());
+        Self::new(Path::new(&db_path).join("keystore"))
+    }
+
+    /// Use different Argon2id parameters for newly saved identities
+    #[must_use]
+    pub const fn with_params(mut self, params: Params) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Get the keystore directory
+    #[must_use]
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn entry_path(&self, name: &str) -> Result<PathBuf, KeystoreError> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(KeystoreError::InvalidName(name.to_string()));
+        }
+        Ok(self.path.join(format!("{name}.{EXTENSION}")))
+    }
+
+    fn read_entry(&self, name: &str) -> Result<KeystoreEntry, KeystoreError> {
+        let path = self.entry_path(name)?;
+        if !path.exists() {
+            return Err(KeystoreError::NotFound(name.to_string()));
+        }
+        Ok(KeystoreEntry::from_bytes_canonical(&fs::read(path)?)?)
+    }
+
+    /// Write to a private temporary file, then hard link it into place so the
+    /// entry appears complete or not at all, and never replaces another
+    fn write_entry(&self, name: &str, entry: &KeystoreEntry) -> Result<(), KeystoreError> {
+        let path = self.entry_path(name)?;
+        let bytes = entry.to_byte_vec()?;
+        fs::create_dir_all(&self.path)?;
+
+        let tmp = path.with_extension(format!("tmp{}", OsRng.next_u64()));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let written = options.open(&tmp).and_then(|mut file| {
+            file.write_all(bytes.get_bytes())?;
+            file.sync_all()
+        });
+        let linked = written.and_then(|()| fs::hard_link(&tmp, &path));
+        let _ = fs::remove_file(&tmp);
+        match linked {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                Err(KeystoreError::AlreadyExists(name.to_string()))
+            }
+            linked => linked.map_err(KeystoreError::from),
+        }
+    }
+
+    /// Encrypt and store `signer` as `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or taken, or writing fails
+    pub fn save(
+        &self,
+        name: &str,
+        signer: &Ed25519Signer,
+        passphrase: &str,
+    ) -> Result<PublicAddress, KeystoreError> {
+        self.entry_path(name)?;
+        let entry = KeystoreEntry::seal(signer, passphrase, &self.params)?;
+        self.write_entry(name, &entry)?;
+        Ok(entry.address)
+    }
+
+    /// Decrypt the identity stored as `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such identity, the passphrase is wrong or
+    /// the file is corrupt
+    pub fn load(&self, name: &str, passphrase: &str) -> Result<Ed25519Signer, KeystoreError> {
+        self.read_entry(name)?.open(passphrase)
+    }
+
+    /// Get the address of the identity stored as `name` without decrypting it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such identity or the file is corrupt
+    pub fn address(&self, name: &str) -> Result<PublicAddress, KeystoreError> {
+        Ok(self.read_entry(name)?.address)
+    }
+
+    /// Names of every stored identity, sorted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the keystore directory cannot be read
+    pub fn list(&self) -> Result<Vec<String>, KeystoreError> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        let mut names = vec![];
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Get the encrypted form of `name` for moving to another keystore
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such identity or the file is corrupt
+    pub fn export(&self, name: &str) -> Result<ByteVec, KeystoreError> {
+        Ok(self.read_entry(name)?.to_byte_vec()?)
+    }
+
+    /// Store an identity produced by `export` as `name`
+    ///
+    /// The passphrase stays the one it was exported with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not an exported identity, or the name is
+    /// invalid or taken
+    pub fn import(&self, name: &str, exported: &ByteVec) -> Result<PublicAddress, KeystoreError> {
+        let entry = KeystoreEntry::from_bytes_canonical(exported.get_bytes())?;
+        entry.check_params()?;
+        self.write_entry(name, &entry)?;
+        Ok(entry.address)
+    }
+
+    /// Delete the identity stored as `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such identity or deleting fails
+    pub fn remove(&self, name: &str) -> Result<(), KeystoreError> {
+        let path = self.entry_path(name)?;
+        if !path.exists() {
+            return Err(KeystoreError::NotFound(name.to_string()));
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so the tests do not spend seconds in Argon2
+    fn test_keystore(label: &str) -> Keystore {
+        let path =
+            std::env::temp_dir().join(format!("subversive-keystore-{label}-{}", OsRng.next_u64()));
+        let params = Params::new(256, 1, 1, Some(SEED_LEN)).unwrap_or_else(|_| unreachable!());
+        Keystore::new(path).with_params(params)
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let keystore = test_keystore("save");
+        let signer = Ed25519Signer::new_random();
+
+        let address = keystore
+            .save("forum-main", &signer, "correct horse")
+            .unwrap_or_else(|e| panic!("save: {e}"));
+        assert_eq!(
+            address,
+            PublicAddress::try_from(&signer).unwrap_or_else(|_| unreachable!())
+        );
+        assert_eq!(keystore.address("forum-main").ok().as_ref(), Some(&address));
+
+        let loaded = keystore
+            .load("forum-main", "correct horse")
+            .unwrap_or_else(|e| panic!("load: {e}"));
+        assert_eq!(loaded.get_verifying_key(), signer.get_verifying_key());
+
+        assert_eq!(
+            keystore.load("forum-main", "wrong").err(),
+            Some(KeystoreError::BadPassphrase)
+        );
+        let _ = fs::remove_dir_all(keystore.get_path());
+    }
+
+    #[test]
+    fn test_multiple_identities() {
+        let keystore = test_keystore("list");
+        assert_eq!(keystore.list().ok(), Some(vec![]));
+
+        for name in ["work", "home", "alt_1"] {
+            keystore
+                .save(name, &Ed25519Signer::new_random(), "pass")
+                .unwrap_or_else(|e| panic!("save {name}: {e}"));
+        }
+        assert_eq!(
+            keystore.list().ok(),
+            Some(vec![
+                "alt_1".to_string(),
+                "home".to_string(),
+                "work".to_string()
+            ])
+        );
+
+        assert_eq!(
+            keystore
+                .save("home", &Ed25519Signer::new_random(), "pass")
+                .err(),
+            Some(KeystoreError::AlreadyExists("home".to_string()))
+        );
+        assert_eq!(
+            keystore
+                .save("../escape", &Ed25519Signer::new_random(), "pass")
+                .err(),
+            Some(KeystoreError::InvalidName("../escape".to_string()))
+        );
+
+        keystore
+            .remove("home")
+            .unwrap_or_else(|e| panic!("remove: {e}"));
+        assert_eq!(
+            keystore.load("home", "pass").err(),
+            Some(KeystoreError::NotFound("home".to_string()))
+        );
+        let _ = fs::remove_dir_all(keystore.get_path());
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let source = test_keystore("export");
+        let target = test_keystore("import");
+        let signer = Ed25519Signer::new_random();
+
+        let address = source
+            .save("laptop", &signer, "pass")
+            .unwrap_or_else(|e| panic!("save: {e}"));
+        let exported = source
+            .export("laptop")
+            .unwrap_or_else(|e| panic!("export: {e}"));
+
+        let imported = target
+            .import("desktop", &exported)
+            .unwrap_or_else(|e| panic!("import: {e}"));
+        assert_eq!(imported, address);
+        let loaded = target
+            .load("desktop", "pass")
+            .unwrap_or_else(|e| panic!("load: {e}"));
+        assert_eq!(loaded.get_verifying_key(), signer.get_verifying_key());
+
+        let garbage = ByteVec::new(vec![1, 2, 3].into());
+        assert!(matches!(
+            target.import("garbage", &garbage),
+            Err(KeystoreError::Corrupt(_))
+        ));
+
+        let _ = fs::remove_dir_all(source.get_path());
+        let _ = fs::remove_dir_all(target.get_path());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_entry_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let keystore = test_keystore("mode");
+        keystore
+            .save("private", &Ed25519Signer::new_random(), "pass")
+            .unwrap_or_else(|e| panic!("save: {e}"));
+        let mode = fs::metadata(keystore.get_path().join("private.key"))
+            .unwrap_or_else(|e| panic!("metadata: {e}"))
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(keystore.list().ok(), Some(vec!["private".to_string()]));
+        let _ = fs::remove_dir_all(keystore.get_path());
+    }
+
+    #[test]
+    fn test_rejects_costly_params() {
+        let keystore = test_keystore("costly");
+        let signer = Ed25519Signer::new_random();
+        keystore
+            .save("costly", &signer, "pass")
+            .unwrap_or_else(|e| panic!("save: {e}"));
+        let mut entry = keystore
+            .read_entry("costly")
+            .unwrap_or_else(|e| panic!("read: {e}"));
+        entry.memory_cost = u64::from(u32::MAX);
+        let crafted = entry.to_byte_vec().unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(matches!(
+            keystore.import("crafted", &crafted),
+            Err(KeystoreError::Corrupt(_))
+        ));
+        assert!(matches!(entry.open("pass"), Err(KeystoreError::Corrupt(_))));
+        let _ = fs::remove_dir_all(keystore.get_path());
+    }
+
+    #[test]
+    fn test_from_config() {
+        crate::config::CONFIG.with(|config| config.borrow_mut().set_db_path("./tmp/db"));
+        assert_eq!(
+            Keystore::from_config().get_path(),
+            Path::new("./tmp/db/keystore")
+        );
+    }
+}
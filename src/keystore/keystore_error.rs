@@ -0,0 +1,51 @@
+use std::fmt::Display;
+
+/// Why a keystore operation failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeystoreError {
+    /// Identity names may only use letters, digits, `-` and `_`
+    InvalidName(String),
+    /// No identity with this name
+    NotFound(String),
+    /// An identity with this name is already stored
+    AlreadyExists(String),
+    /// The passphrase does not decrypt the identity
+    BadPassphrase,
+    /// A stored or imported identity could not be decoded
+    Corrupt(String),
+    /// Reading or writing the keystore directory failed
+    Io(String),
+    /// The words are not a valid mnemonic
+    BadMnemonic(String),
+    /// The derivation path is malformed or not fully hardened
+    BadPath(String),
+}
+
+impl Display for KeystoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "Invalid identity name {name:?}"),
+            Self::NotFound(name) => write!(f, "No identity named {name:?}"),
+            Self::AlreadyExists(name) => write!(f, "Identity {name:?} already exists"),
+            Self::BadPassphrase => write!(f, "Wrong passphrase"),
+            Self::Corrupt(reason) => write!(f, "Corrupt identity: {reason}"),
+            Self::Io(reason) => write!(f, "Keystore I/O failed: {reason}"),
+            Self::BadMnemonic(reason) => write!(f, "Invalid mnemonic: {reason}"),
+            Self::BadPath(path) => write!(f, "Invalid derivation path {path:?}"),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value.to_string())
+    }
+}
+
+impl From<base_xx::SerialiseError> for KeystoreError {
+    fn from(value: base_xx::SerialiseError) -> Self {
+        Self::Corrupt(value.to_string())
+    }
+}
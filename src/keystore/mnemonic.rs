@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use rand_core::{OsRng, RngCore};
+use simple_sign::Ed25519Signer;
+
+use crate::keystore::hd_key::IDENTITY_PATH;
+use crate::keystore::{ExtendedKey, KeystoreError};
+
+/// Entropy behind a twelve word phrase
+const ENTROPY_LEN: usize = 16;
+
+/// BIP39 seed phrase
+///
+/// Every identity of a person is derived from the phrase, so writing down the
+/// twelve words is enough to restore all of their `PublicAddress` identities.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    inner: bip39::Mnemonic,
+}
+
+impl Mnemonic {
+    /// Generate a random twelve word phrase
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entropy is rejected, which does not happen in practice
+    pub fn generate() -> Result<Self, KeystoreError> {
+        let mut entropy = [0u8; ENTROPY_LEN];
+        OsRng.fill_bytes(&mut entropy);
+        Self::from_entropy(&entropy)
+    }
+
+    /// Build the phrase encoding `entropy`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error unless `entropy` is 16 to 32 bytes in steps of 4
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, KeystoreError> {
+        bip39::Mnemonic::from_entropy(entropy)
+            .map(|inner| Self { inner })
+            .map_err(|e| KeystoreError::BadMnemonic(e.to_string()))
+    }
+
+    /// Get the words of the phrase
+    #[must_use]
+    pub fn get_words(&self) -> Vec<&'static str> {
+        self.inner.words().collect()
+    }
+
+    /// BIP39 seed, stretched from the phrase and an optional passphrase
+    #[must_use]
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        self.inner.to_seed(passphrase)
+    }
+
+    /// Master key for this phrase
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the master key cannot be derived
+    pub fn to_master_key(&self, passphrase: &str) -> Result<ExtendedKey, KeystoreError> {
+        ExtendedKey::from_seed(&self.to_seed(passphrase))
+    }
+
+    /// Signer for identity `account`, at `m/44'/9393'/<account>'`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `account` does not fit a hardened index
+    pub fn derive_identity(
+        &self,
+        passphrase: &str,
+        account: u32,
+    ) -> Result<Ed25519Signer, KeystoreError> {
+        Ok(self
+            .to_master_key(passphrase)?
+            .derive_path(&format!("{IDENTITY_PATH}/{account}'"))?
+            .to_signer())
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = KeystoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        bip39::Mnemonic::parse_in(bip39::Language::English, s)
+            .map(|inner| Self { inner })
+            .map_err(|e| KeystoreError::BadMnemonic(e.to_string()))
+    }
+}
+
+impl std::fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+impl std::fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mnemonic").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::public_address::PublicAddress;
+    use crate::test_util::hex;
+
+    /// First vector of the reference BIP39 test set
+    #[test]
+    fn test_bip39_vector() {
+        let mnemonic = Mnemonic::from_entropy(&[0; 16]).unwrap_or_else(|e| panic!("entropy: {e}"));
+        assert_eq!(
+            mnemonic.to_string(),
+            "abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon about"
+        );
+        assert_eq!(
+            hex(&mnemonic.to_seed("TREZOR")),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+
+    #[test]
+    fn test_restore_identities() {
+        let mnemonic = Mnemonic::generate().unwrap_or_else(|e| panic!("generate: {e}"));
+        assert_eq!(mnemonic.get_words().len(), 12);
+
+        let restored =
+            Mnemonic::from_str(&mnemonic.to_string()).unwrap_or_else(|e| panic!("parse: {e}"));
+        assert_eq!(restored, mnemonic);
+
+        let address = |m: &Mnemonic, account| {
+            let signer = m
+                .derive_identity("", account)
+                .unwrap_or_else(|e| panic!("derive: {e}"));
+            PublicAddress::try_from(&signer).unwrap_or_else(|_| unreachable!())
+        };
+        assert_eq!(address(&mnemonic, 0), address(&restored, 0));
+        assert_eq!(address(&mnemonic, 1), address(&restored, 1));
+        assert_ne!(address(&mnemonic, 0), address(&mnemonic, 1));
+    }
+
+    #[test]
+    fn test_bad_mnemonic() {
+        // last word breaks the checksum
+        let phrase = "abandon abandon abandon abandon abandon abandon \
+                      abandon abandon abandon abandon abandon abandon";
+        assert!(matches!(
+            Mnemonic::from_str(phrase),
+            Err(KeystoreError::BadMnemonic(_))
+        ));
+        assert!(Mnemonic::from_str("not a seed phrase").is_err());
+    }
+}
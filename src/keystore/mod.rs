@@ -0,0 +1,17 @@
+/// Encrypted identity storage
+#[allow(clippy::module_inception)]
+pub mod keystore;
+
+/// Hierarchical key derivation
+pub mod hd_key;
+
+/// Keystore errors
+pub mod keystore_error;
+
+/// Mnemonic seed phrases
+pub mod mnemonic;
+
+pub use hd_key::ExtendedKey;
+pub use keystore::Keystore;
+pub use keystore_error::KeystoreError;
+pub use mnemonic::Mnemonic;
@@ -0,0 +1,34 @@
+use crate::serialise::{RleDecode, RleEncode};
+
+/// State of one address in the ledger
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, RleEncode, RleDecode)]
+pub struct Account {
+    balance: u64,
+    nonce: u64,
+}
+
+impl Account {
+    /// Create an account
+    #[must_use]
+    pub const fn new(balance: u64, nonce: u64) -> Self {
+        Self { balance, nonce }
+    }
+
+    /// Get balance
+    #[must_use]
+    pub const fn get_balance(&self) -> u64 {
+        self.balance
+    }
+
+    /// Nonce the next transaction from this account must carry
+    #[must_use]
+    pub const fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Whether the account holds nothing and has sent nothing
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.balance == 0 && self.nonce == 0
+    }
+}
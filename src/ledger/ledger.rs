@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use base_xx::SerialiseError;
+use chrono::{DateTime, Utc};
+use slahasher::{Hash, HashAlgorithm};
+
+use crate::address::public_address::PublicAddress;
+use crate::address::KeyAlgorithm;
+use crate::ledger::{Account, LedgerError};
+use crate::serialise::{RLEByteVec, RleEncode, RleEncodeField};
+use crate::transactions::transaction::TRANSACTION_VERSION_LEGACY;
+use crate::transactions::Transaction;
+
+/// Per-account state built by applying transactions in order
+///
+/// Each sender's transactions must carry nonces 0, 1, 2, ... in turn, so a
+/// captured transaction cannot be applied a second time, and must be covered
+/// by the sender's balance.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    accounts: BTreeMap<Rc<PublicAddress>, Account>,
+}
+
+/// Accounts as they were before `Ledger::apply_block`, used to undo the block
+#[derive(Debug, Default)]
+pub struct BlockUndo {
+    previous: BTreeMap<Rc<PublicAddress>, Option<Account>>,
+}
+
+impl BlockUndo {
+    fn record(&mut self, ledger: &Ledger, address: &Rc<PublicAddress>) {
+        if !self.previous.contains_key(address) {
+            self.previous
+                .insert(Rc::clone(address), ledger.accounts.get(address).copied());
+        }
+    }
+}
+
+impl Ledger {
+    /// Create an empty ledger
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// State of `address`, empty if it has never been seen
+    #[must_use]
+    pub fn get_account(&self, address: &PublicAddress) -> Account {
+        self.accounts.get(address).copied().unwrap_or_default()
+    }
+
+    /// Get balance of `address`
+    #[must_use]
+    pub fn get_balance(&self, address: &PublicAddress) -> u64 {
+        self.get_account(address).get_balance()
+    }
+
+    /// Nonce the next transaction from `address` must carry
+    #[must_use]
+    pub fn get_next_nonce(&self, address: &PublicAddress) -> u64 {
+        self.get_account(address).get_nonce()
+    }
+
+    /// Add `amount` to the balance of `address`, for genesis allocations and rewards
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the ledger unchanged, if the balance overflows
+    /// or `address` is a shared account
+    pub fn credit(&mut self, address: &Rc<PublicAddress>, amount: u64) -> Result<(), LedgerError> {
+        Self::check_recipient(address)?;
+        let account = self.get_account(address);
+        let balance = account
+            .get_balance()
+            .checked_add(amount)
+            .ok_or(LedgerError::Overflow)?;
+        self.accounts.insert(
+            Rc::clone(address),
+            Account::new(balance, account.get_nonce()),
+        );
+        Ok(())
+    }
+
+    /// Check that `transaction` can be applied next
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction is legacy or its nonce is not the
+    /// sender's next nonce
+    pub fn check_nonce(&self, transaction: &Transaction) -> Result<(), LedgerError> {
+        if transaction.get_version() == TRANSACTION_VERSION_LEGACY {
+            return Err(LedgerError::LegacyTransaction);
+        }
+        let expected = self.get_next_nonce(transaction.get_from());
+        if transaction.get_nonce() != expected {
+            return Err(LedgerError::BadNonce {
+                expected,
+                found: transaction.get_nonce(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that `address` can be paid
+    ///
+    /// Shared accounts are refused until a `MultisigTransaction` can spend
+    /// from them; until then anything sent to one would be lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `address` is a multisig address
+    pub fn check_recipient(address: &PublicAddress) -> Result<(), LedgerError> {
+        if address.get_algorithm() == KeyAlgorithm::Multisig {
+            return Err(LedgerError::MultisigRecipient);
+        }
+        Ok(())
+    }
+
+    /// Apply a verified transaction, moving `amount` and removing `fee` from the sender
+    ///
+    /// The fee is not credited here; `apply_block` pays it to the block signer.
+    /// `now` is the time of the block the transaction is included in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the ledger unchanged, if the transaction is
+    /// still time-locked at `now`, the nonce is wrong, the recipient is a
+    /// shared account, the sender cannot afford it or the recipient's balance
+    /// would overflow
+    pub fn apply(
+        &mut self,
+        transaction: &Transaction,
+        now: &DateTime<Utc>,
+    ) -> Result<(), LedgerError> {
+        if let Some(valid_after) = transaction.get_valid_after().filter(|time| *time > now) {
+            return Err(LedgerError::Immature(*valid_after));
+        }
+        self.check_nonce(transaction)?;
+
+        let from = transaction.get_from();
+        let to = transaction.get_to();
+        Self::check_recipient(to)?;
+        let sender = self.get_account(from);
+        let needed = transaction.get_total_cost().ok_or(LedgerError::Overflow)?;
+        let sender_balance =
+            sender
+                .get_balance()
+                .checked_sub(needed)
+                .ok_or_else(|| LedgerError::Overdraft {
+                    balance: sender.get_balance(),
+                    needed,
+                })?;
+        let sender = Account::new(sender_balance, sender.get_nonce() + 1);
+
+        // a transfer to yourself only pays the fee
+        let recipient = if from == to {
+            sender
+        } else {
+            self.get_account(to)
+        };
+        let recipient_balance = recipient
+            .get_balance()
+            .checked_add(transaction.get_amount())
+            .ok_or(LedgerError::Overflow)?;
+        let recipient = Account::new(recipient_balance, recipient.get_nonce());
+
+        if from == to {
+            self.accounts.insert(Rc::clone(from), recipient);
+        } else {
+            self.accounts.insert(Rc::clone(from), sender);
+            self.accounts.insert(Rc::clone(to), recipient);
+        }
+        Ok(())
+    }
+
+    /// Apply every transaction of a block made at `time` and pay their fees to `signer`
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failure, leaving the ledger as it was before the block
+    pub fn apply_block<'a>(
+        &mut self,
+        signer: &Rc<PublicAddress>,
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        time: &DateTime<Utc>,
+    ) -> Result<BlockUndo, LedgerError> {
+        let mut undo = BlockUndo::default();
+        let result = self.apply_all(signer, transactions, time, &mut undo);
+        match result {
+            Ok(()) => Ok(undo),
+            Err(e) => {
+                self.undo_block(undo);
+                Err(e)
+            }
+        }
+    }
+
+    fn apply_all<'a>(
+        &mut self,
+        signer: &Rc<PublicAddress>,
+        transactions: impl IntoIterator<Item = &'a Transaction>,
+        time: &DateTime<Utc>,
+        undo: &mut BlockUndo,
+    ) -> Result<(), LedgerError> {
+        let mut fees = 0u64;
+        for transaction in transactions {
+            undo.record(self, transaction.get_from());
+            undo.record(self, transaction.get_to());
+            self.apply(transaction, time)?;
+            fees = fees
+                .checked_add(transaction.get_fee())
+                .ok_or(LedgerError::Overflow)?;
+        }
+        undo.record(self, signer);
+        self.credit(signer, fees)
+    }
+
+    /// Revert a block applied by `apply_block`
+    ///
+    /// Blocks must be undone newest first.
+    pub fn undo_block(&mut self, undo: BlockUndo) {
+        for (address, account) in undo.previous {
+            match account {
+                Some(account) => self.accounts.insert(address, account),
+                None => self.accounts.remove(&address),
+            };
+        }
+    }
+
+    /// KECCAK512 hash of every non-empty account, in address order
+    ///
+    /// Two ledgers holding the same balances and nonces always hash the same,
+    /// whatever order they were built in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state cannot be encoded or hashed
+    pub fn state_hash(&self) -> Result<Arc<Hash>, SerialiseError> {
+        Hash::try_hash(Arc::new(self.to_byte_vec()?), HashAlgorithm::KECCAK512)
+    }
+}
+
+/// Encodes as RLE fields `[address][account]` for every non-empty account
+impl RleEncode for Ledger {
+    fn to_rle(&self) -> Result<RLEByteVec, SerialiseError> {
+        let mut rle = RLEByteVec::default();
+        for (address, account) in &self.accounts {
+            if !account.is_empty() {
+                address.encode_field(&mut rle)?;
+                account.encode_field(&mut rle)?;
+            }
+        }
+        Ok(rle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::MultisigPolicy;
+    use chrono::{TimeDelta, Timelike};
+    use simple_sign::Ed25519Signer;
+
+    fn address() -> Rc<PublicAddress> {
+        Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        )
+    }
+
+    #[test]
+    fn test_nonces_must_be_sequential() {
+        let mut ledger = Ledger::new();
+        let (alice, bob) = (address(), address());
+        let now = Utc::now();
+        let transfer = |nonce| Transaction::new(Rc::clone(&alice), Rc::clone(&bob), 10, now, nonce);
+        ledger
+            .credit(&alice, 100)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+
+        assert_eq!(
+            ledger.apply(&transfer(1), &now),
+            Err(LedgerError::BadNonce {
+                expected: 0,
+                found: 1
+            })
+        );
+        assert_eq!(ledger.apply(&transfer(0), &now), Ok(()));
+        assert_eq!(ledger.get_next_nonce(&alice), 1);
+        assert_eq!(ledger.get_next_nonce(&bob), 0);
+
+        // the identical transaction cannot be replayed
+        assert_eq!(
+            ledger.apply(&transfer(0), &now),
+            Err(LedgerError::BadNonce {
+                expected: 1,
+                found: 0
+            })
+        );
+        assert_eq!(ledger.apply(&transfer(1), &now), Ok(()));
+        assert_eq!(ledger.get_next_nonce(&alice), 2);
+    }
+
+    #[test]
+    fn test_balances() {
+        let mut ledger = Ledger::new();
+        let (alice, bob) = (address(), address());
+        let now = Utc::now();
+        ledger
+            .credit(&alice, 50)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+
+        let transfer = Transaction::new(Rc::clone(&alice), Rc::clone(&bob), 40, now, 0).with_fee(5);
+        assert_eq!(ledger.apply(&transfer, &now), Ok(()));
+        assert_eq!(ledger.get_balance(&alice), 5);
+        assert_eq!(ledger.get_balance(&bob), 40);
+
+        let overdraft = Transaction::new(Rc::clone(&alice), Rc::clone(&bob), 5, now, 1).with_fee(1);
+        assert_eq!(
+            ledger.apply(&overdraft, &now),
+            Err(LedgerError::Overdraft {
+                balance: 5,
+                needed: 6
+            })
+        );
+        assert_eq!(ledger.get_next_nonce(&alice), 1);
+
+        assert_eq!(ledger.credit(&bob, u64::MAX), Err(LedgerError::Overflow));
+        ledger
+            .credit(&alice, u64::MAX - 5)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+        let overflow = Transaction::new(Rc::clone(&alice), Rc::clone(&bob), u64::MAX - 10, now, 1);
+        assert_eq!(ledger.apply(&overflow, &now), Err(LedgerError::Overflow));
+        assert_eq!(ledger.get_balance(&bob), 40);
+    }
+
+    #[test]
+    fn test_multisig_recipient_refused() {
+        let mut ledger = Ledger::new();
+        let alice = address();
+        let now = Utc::now();
+        let policy = MultisigPolicy::new(1, vec![Rc::clone(&alice), address()])
+            .unwrap_or_else(|e| panic!("policy: {e}"));
+        let shared = Rc::new(policy.address().unwrap_or_else(|e| panic!("address: {e}")));
+        ledger
+            .credit(&alice, 50)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+
+        let transfer = Transaction::new(Rc::clone(&alice), Rc::clone(&shared), 40, now, 0);
+        assert_eq!(
+            ledger.apply(&transfer, &now),
+            Err(LedgerError::MultisigRecipient)
+        );
+        assert_eq!(
+            ledger.credit(&shared, 1),
+            Err(LedgerError::MultisigRecipient)
+        );
+        assert_eq!(ledger.get_balance(&alice), 50);
+        assert_eq!(ledger.get_next_nonce(&alice), 0);
+    }
+
+    #[test]
+    fn test_block_undo() {
+        let mut ledger = Ledger::new();
+        let (alice, bob, signer) = (address(), address(), address());
+        let now = Utc::now();
+        ledger
+            .credit(&alice, 100)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+        let before = ledger.state_hash().unwrap_or_else(|e| panic!("hash: {e}"));
+
+        let block = [
+            Transaction::new(Rc::clone(&alice), Rc::clone(&bob), 30, now, 0).with_fee(2),
+            Transaction::new(Rc::clone(&bob), Rc::clone(&alice), 10, now, 0).with_fee(1),
+        ];
+        let undo = ledger
+            .apply_block(&signer, &block, &now)
+            .unwrap_or_else(|e| panic!("apply: {e}"));
+        assert_eq!(ledger.get_balance(&alice), 78);
+        assert_eq!(ledger.get_balance(&bob), 19);
+        assert_eq!(ledger.get_balance(&signer), 3);
+        assert_ne!(ledger.state_hash().ok(), Some(Arc::clone(&before)));
+
+        ledger.undo_block(undo);
+        assert_eq!(ledger.state_hash().ok(), Some(Arc::clone(&before)));
+        assert_eq!(ledger.get_next_nonce(&alice), 0);
+
+        // a failing block leaves no partial effects
+        let bad = [
+            Transaction::new(Rc::clone(&alice), Rc::clone(&bob), 30, now, 0),
+            Transaction::new(Rc::clone(&alice), Rc::clone(&bob), 300, now, 1),
+        ];
+        assert!(ledger.apply_block(&signer, &bad, &now).is_err());
+        assert_eq!(ledger.state_hash().ok(), Some(before));
+    }
+
+    #[test]
+    fn test_state_hash_is_order_independent() {
+        let (alice, bob) = (address(), address());
+        let mut first = Ledger::new();
+        let mut second = Ledger::new();
+        for (ledger, order) in [(&mut first, [&alice, &bob]), (&mut second, [&bob, &alice])] {
+            for address in order {
+                ledger
+                    .credit(address, 7)
+                    .unwrap_or_else(|e| panic!("credit: {e}"));
+            }
+        }
+        assert_eq!(first.state_hash().ok(), second.state_hash().ok());
+
+        // empty accounts do not change the hash
+        second
+            .credit(&address(), 0)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+        assert_eq!(first.state_hash().ok(), second.state_hash().ok());
+    }
+
+    #[test]
+    fn test_time_lock() {
+        let mut ledger = Ledger::new();
+        let (alice, bob, signer) = (address(), address(), address());
+        let now = Utc::now();
+        let due = (now + TimeDelta::days(30))
+            .with_nanosecond(0)
+            .unwrap_or_else(|| unreachable!());
+        ledger
+            .credit(&alice, 100)
+            .unwrap_or_else(|e| panic!("credit: {e}"));
+
+        let bounty =
+            Transaction::new(Rc::clone(&alice), Rc::clone(&bob), 50, now, 0).with_valid_after(due);
+        let block = [bounty];
+        assert_eq!(
+            ledger.apply_block(&signer, &block, &now).err(),
+            Some(LedgerError::Immature(due))
+        );
+        assert_eq!(ledger.get_balance(&bob), 0);
+
+        ledger
+            .apply_block(&signer, &block, &due)
+            .unwrap_or_else(|e| panic!("apply: {e}"));
+        assert_eq!(ledger.get_balance(&bob), 50);
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+
+/// Why the ledger rejected a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LedgerError {
+    /// Legacy transactions carry no nonce and could be replayed
+    LegacyTransaction,
+    /// The nonce is not the next one expected for the sender
+    BadNonce {
+        /// Next nonce for the sender
+        expected: u64,
+        /// Nonce on the transaction
+        found: u64,
+    },
+    /// The sender cannot afford the amount plus fee
+    Overdraft {
+        /// Sender balance
+        balance: u64,
+        /// Amount plus fee
+        needed: u64,
+    },
+    /// A balance or fee total would exceed `u64::MAX`
+    Overflow,
+    /// The transaction is time-locked until after the block time
+    Immature(DateTime<Utc>),
+    /// The recipient is a shared account, which cannot spend yet
+    MultisigRecipient,
+}
+
+impl Display for LedgerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LegacyTransaction => write!(f, "Legacy transactions have no nonce"),
+            Self::BadNonce { expected, found } => {
+                write!(f, "Expected nonce {expected}, found {found}")
+            }
+            Self::Overdraft { balance, needed } => {
+                write!(f, "Balance {balance} cannot cover {needed}")
+            }
+            Self::Overflow => write!(f, "Balance overflow"),
+            Self::Immature(valid_after) => write!(f, "Not valid before {valid_after}"),
+            Self::MultisigRecipient => write!(f, "Shared accounts cannot receive funds yet"),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
@@ -0,0 +1,13 @@
+/// Per-address ledger state
+pub mod account;
+
+/// Account state built from applied transactions
+#[allow(clippy::module_inception)]
+pub mod ledger;
+
+/// Ledger errors
+pub mod ledger_error;
+
+pub use account::Account;
+pub use ledger::{BlockUndo, Ledger};
+pub use ledger_error::LedgerError;
@@ -8,12 +8,39 @@
 //! - Logging utilities with colored output
 //! - Proof of concept implementations
 
+// lets the derive macros refer to `::subversive` from inside this crate
+extern crate self as subversive;
+
 /// Addressing system
 pub mod address;
 
+/// Chain of accepted blocks
+pub mod chain;
+
+/// Configuration
+pub mod config;
+
+/// Slot lottery and fork choice
+pub mod consensus;
+
 /// Game system
 pub mod game;
 
+/// Genesis specification and network presets
+pub mod genesis;
+
+/// Encrypted key storage
+pub mod keystore;
+
+/// Account ledger
+pub mod ledger;
+
+/// Pending transaction pool
+pub mod mempool;
+
+/// Merkle trees over transaction ids
+pub mod merkle;
+
 /// Transactions system
 pub mod transactions;
 
@@ -23,3 +50,7 @@ pub mod serialise;
 #[cfg(test)]
 /// Proof of concept implementations and examples
 pub mod poc;
+
+#[cfg(test)]
+/// Helpers shared by unit tests
+pub mod test_util;
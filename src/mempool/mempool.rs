@@ -0,0 +1,801 @@
+use std::collections::{BTreeMap, BinaryHeap};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use slahasher::Hash;
+
+use crate::address::public_address::PublicAddress;
+use crate::game::Block;
+use crate::ledger::Ledger;
+use crate::mempool::MempoolError;
+use crate::transactions::transaction::TRANSACTION_VERSION_LEGACY;
+use crate::transactions::{Cancellation, SignedTransaction, Transaction};
+
+/// Default number of transactions held
+pub const DEFAULT_MAX_SIZE: usize = 10_000;
+
+/// Default age after which a pending transaction is dropped, in seconds
+pub const DEFAULT_MAX_AGE_SECS: i64 = 60 * 60;
+
+/// Furthest a sender's nonce may run ahead of the ledger's next nonce
+pub const MAX_NONCE_GAP: u64 = 64;
+
+/// Furthest a timestamp may be ahead of the local clock, in seconds
+pub const MAX_FUTURE_SECS: i64 = 10 * 60;
+
+/// Longest a transaction may be time-locked for from the local clock, in seconds
+pub const MAX_LOCK_SECS: i64 = 90 * 24 * 60 * 60;
+
+/// Verified transactions waiting to be included in a block
+///
+/// Keyed by transaction id, and indexed by sender and nonce so each sender's
+/// transactions are handed to block building in nonce order. Time-locked
+/// transactions are held until they mature, and can be replaced by their
+/// sender until then.
+#[derive(Debug)]
+pub struct Mempool {
+    transactions: BTreeMap<Arc<Hash>, SignedTransaction>,
+    by_sender: BTreeMap<Rc<PublicAddress>, BTreeMap<u64, Arc<Hash>>>,
+    /// Cancelled ids, refused until they would have expired anyway
+    cancelled: BTreeMap<Arc<Hash>, DateTime<Utc>>,
+    max_size: usize,
+    max_age: Duration,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SIZE, Duration::seconds(DEFAULT_MAX_AGE_SECS))
+    }
+}
+
+impl Mempool {
+    /// Create a mempool holding at most `max_size` transactions no older than `max_age`
+    #[must_use]
+    pub const fn new(max_size: usize, max_age: Duration) -> Self {
+        Self {
+            transactions: BTreeMap::new(),
+            by_sender: BTreeMap::new(),
+            cancelled: BTreeMap::new(),
+            max_size,
+            max_age,
+        }
+    }
+
+    /// Number of pending transactions
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Whether nothing is pending
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Whether the transaction with `id` is pending
+    #[must_use]
+    pub fn contains(&self, id: &Hash) -> bool {
+        self.transactions.contains_key(id)
+    }
+
+    /// Get the pending transaction with `id`
+    #[must_use]
+    pub fn get(&self, id: &Hash) -> Option<&SignedTransaction> {
+        self.transactions.get(id)
+    }
+
+    /// Time-locked transactions age from when they mature
+    fn is_expired(&self, transaction: &Transaction, now: DateTime<Utc>) -> bool {
+        Self::age_from(transaction) < now - self.max_age
+    }
+
+    fn age_from(transaction: &Transaction) -> DateTime<Utc> {
+        let timestamp = transaction.get_timestamp();
+        *transaction
+            .get_valid_after()
+            .map_or(timestamp, |valid_after| valid_after.max(timestamp))
+    }
+
+    /// Add a signed transaction, returning its id
+    ///
+    /// The sender's ledger balance must cover this transaction together with
+    /// the rest of their pending queue, the nonce may be at most
+    /// `MAX_NONCE_GAP` ahead and a time lock at most `MAX_LOCK_SECS` away, so
+    /// nobody can fill the pool with transactions that will never be selected.
+    /// A pending transaction with the same sender and nonce is replaced only if
+    /// the new one pays a higher fee, or the pending one is still time-locked
+    /// and the new one is not. When the pool is full the transaction
+    /// paying the lowest fee is evicted, taking only the last of each sender's
+    /// queue so no sender is left with a gap.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is invalid, the transaction is already
+    /// pending, cancelled, legacy, stale, expired, dated or locked too far in the
+    /// future, sent to a shared account, unfunded or too far ahead of the
+    /// sender's nonce, or the pool is full of better paying ones
+    pub fn insert(
+        &mut self,
+        signed: SignedTransaction,
+        ledger: &Ledger,
+        now: DateTime<Utc>,
+    ) -> Result<Arc<Hash>, MempoolError> {
+        if !signed.verify() {
+            return Err(MempoolError::InvalidSignature);
+        }
+        let id = Arc::clone(signed.get_signature().get_id());
+        if self.contains(&id) {
+            return Err(MempoolError::Duplicate);
+        }
+        if self.cancelled.contains_key(&id) {
+            return Err(MempoolError::Cancelled);
+        }
+
+        let transaction = signed.get_transaction();
+        if self.is_expired(transaction, now) {
+            return Err(MempoolError::Expired);
+        }
+        if *transaction.get_timestamp() > now + Duration::seconds(MAX_FUTURE_SECS) {
+            return Err(MempoolError::FutureTimestamp);
+        }
+        if transaction
+            .get_valid_after()
+            .is_some_and(|valid_after| *valid_after > now + Duration::seconds(MAX_LOCK_SECS))
+        {
+            return Err(MempoolError::LockTooLong);
+        }
+        if transaction.get_version() == TRANSACTION_VERSION_LEGACY {
+            return Err(MempoolError::LegacyTransaction);
+        }
+        let from = transaction.get_from();
+        let nonce = transaction.get_nonce();
+        let next = ledger.get_next_nonce(from);
+        if nonce < next {
+            return Err(MempoolError::StaleNonce(nonce));
+        }
+        if nonce - next > MAX_NONCE_GAP {
+            return Err(MempoolError::NonceTooFar(nonce));
+        }
+        Ledger::check_recipient(transaction.get_to())
+            .map_err(|_| MempoolError::MultisigRecipient)?;
+        self.check_funds(transaction, ledger)?;
+
+        let existing = self
+            .by_sender
+            .get(from)
+            .and_then(|queue| queue.get(&nonce))
+            .map(Arc::clone);
+        if let Some(existing) = existing {
+            let replaceable = self.get(&existing).is_none_or(|pending| {
+                let pending = pending.get_transaction();
+                transaction.get_fee() > pending.get_fee()
+                    || (!pending.is_mature(&now) && transaction.get_valid_after().is_none())
+            });
+            if !replaceable {
+                return Err(MempoolError::NonceConflict(nonce));
+            }
+            self.remove(&existing);
+        }
+
+        self.by_sender
+            .entry(Rc::clone(from))
+            .or_default()
+            .insert(nonce, Arc::clone(&id));
+        self.transactions.insert(Arc::clone(&id), signed);
+
+        if self.len() > self.max_size {
+            if let Some(evicted) = self.eviction_candidate() {
+                self.remove(&evicted);
+                if evicted == id {
+                    return Err(MempoolError::Full);
+                }
+            }
+        }
+        Ok(id)
+    }
+
+    /// Whether the sender can pay for `transaction` and every other pending
+    /// transaction of theirs, not counting one it would replace
+    fn check_funds(&self, transaction: &Transaction, ledger: &Ledger) -> Result<(), MempoolError> {
+        let from = transaction.get_from();
+        let balance = ledger.get_balance(from);
+        let unaffordable = MempoolError::InsufficientFunds {
+            balance,
+            needed: u64::MAX,
+        };
+        let pending = self
+            .by_sender
+            .get(from)
+            .into_iter()
+            .flatten()
+            .filter(|(nonce, _)| **nonce != transaction.get_nonce())
+            .filter_map(|(_, id)| self.get(id))
+            .try_fold(0u64, |total, signed| {
+                total.checked_add(signed.get_transaction().get_total_cost()?)
+            });
+        let needed = pending
+            .and_then(|pending| pending.checked_add(transaction.get_total_cost()?))
+            .ok_or(unaffordable)?;
+        if needed > balance {
+            return Err(MempoolError::InsufficientFunds { balance, needed });
+        }
+        Ok(())
+    }
+
+    /// Lowest fee transaction among the last of each sender's queue
+    fn eviction_candidate(&self) -> Option<Arc<Hash>> {
+        self.by_sender
+            .values()
+            .filter_map(|queue| queue.values().next_back())
+            .min_by_key(|id| {
+                self.get(id)
+                    .map_or(0, |signed| signed.get_transaction().get_fee())
+            })
+            .map(Arc::clone)
+    }
+
+    /// Remove the transaction with `id`, returning it
+    pub fn remove(&mut self, id: &Hash) -> Option<SignedTransaction> {
+        let signed = self.transactions.remove(id)?;
+        let transaction = signed.get_transaction();
+        if let Some(queue) = self.by_sender.get_mut(transaction.get_from()) {
+            queue.remove(&transaction.get_nonce());
+            if queue.is_empty() {
+                self.by_sender.remove(transaction.get_from());
+            }
+        }
+        Some(signed)
+    }
+
+    /// Replace a time-locked transaction with its sender's cancellation,
+    /// returning the id of the cancelling transaction
+    ///
+    /// The cancelling transaction uses the same nonce, so once a block includes
+    /// it the locked one can never be applied, wherever copies of it are held.
+    /// The cancelled id is remembered so it is refused if sent again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction is not pending, has already matured
+    /// at `now`, the cancellation does not match it, or the cancelling
+    /// transaction is refused
+    pub fn cancel(
+        &mut self,
+        cancellation: Cancellation,
+        ledger: &Ledger,
+        now: DateTime<Utc>,
+    ) -> Result<Arc<Hash>, MempoolError> {
+        let id = Arc::clone(cancellation.get_id());
+        let locked = self
+            .get(&id)
+            .ok_or(MempoolError::UnknownTransaction)?
+            .get_transaction();
+        if locked.is_mature(&now) {
+            return Err(MempoolError::Mature);
+        }
+        if !cancellation.cancels(locked) {
+            return Err(MempoolError::InvalidCancellation);
+        }
+        let age_from = Self::age_from(locked);
+        let replacement = self.insert(cancellation.into_transaction(), ledger, now)?;
+        self.cancelled.insert(id, age_from);
+        Ok(replacement)
+    }
+
+    /// Drop everything superseded by an accepted block
+    ///
+    /// Removes the block's transactions and any pending transaction whose nonce
+    /// the ledger, already updated with the block, has moved past.
+    pub fn remove_included(&mut self, block: &Block, ledger: &Ledger) {
+        for signed in block.get_transactions() {
+            if let Ok(id) = signed.get_transaction().id() {
+                self.remove(&id);
+            }
+        }
+        let stale: Vec<Arc<Hash>> = self
+            .by_sender
+            .iter()
+            .flat_map(|(sender, queue)| {
+                let next = ledger.get_next_nonce(sender);
+                queue.range(..next).map(|(_, id)| Arc::clone(id))
+            })
+            .collect();
+        for id in stale {
+            self.remove(&id);
+        }
+    }
+
+    /// Drop transactions older than the maximum age, returning how many
+    ///
+    /// Cancelled ids are forgotten at the same age.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> usize {
+        let cutoff = now - self.max_age;
+        self.cancelled.retain(|_, age_from| *age_from >= cutoff);
+        let expired: Vec<Arc<Hash>> = self
+            .transactions
+            .iter()
+            .filter(|(_, signed)| self.is_expired(signed.get_transaction(), now))
+            .map(|(id, _)| Arc::clone(id))
+            .collect();
+        for id in &expired {
+            self.remove(id);
+        }
+        expired.len()
+    }
+
+    /// Up to `limit` transactions for the next block, highest fee first
+    ///
+    /// A sender's transaction is only offered once every lower nonce down to
+    /// the ledger's next nonce has been selected, so the result can be applied
+    /// in order. Transactions still time-locked at the block time `now` are
+    /// held back, together with the rest of their sender's queue.
+    #[must_use]
+    pub fn select(
+        &self,
+        ledger: &Ledger,
+        limit: usize,
+        now: DateTime<Utc>,
+    ) -> Vec<&SignedTransaction> {
+        let mature = |id: &Arc<Hash>| {
+            self.get(id)
+                .filter(|signed| signed.get_transaction().is_mature(&now))
+        };
+        // (fee, sender, nonce); ties go to the lower sender address
+        let mut ready = BinaryHeap::new();
+        for (sender, queue) in &self.by_sender {
+            let next = ledger.get_next_nonce(sender);
+            if let Some(signed) = queue.get(&next).and_then(mature) {
+                ready.push((
+                    signed.get_transaction().get_fee(),
+                    std::cmp::Reverse(Rc::clone(sender)),
+                    next,
+                ));
+            }
+        }
+
+        let mut selected = Vec::new();
+        while selected.len() < limit {
+            let Some((_, std::cmp::Reverse(sender), nonce)) = ready.pop() else {
+                break;
+            };
+            let Some(queue) = self.by_sender.get(&sender) else {
+                continue;
+            };
+            if let Some(signed) = queue.get(&nonce).and_then(|id| self.get(id)) {
+                selected.push(signed);
+            }
+            if let Some(signed) = queue.get(&(nonce + 1)).and_then(mature) {
+                ready.push((
+                    signed.get_transaction().get_fee(),
+                    std::cmp::Reverse(sender),
+                    nonce + 1,
+                ));
+            }
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::MultisigPolicy;
+    use crate::ledger::LedgerError;
+    use crate::serialise::RLEByteVec;
+    use crate::transactions::TransactionSignature;
+    use base_xx::ByteVec;
+    use simple_sign::Ed25519Signer;
+
+    struct Sender {
+        signer: Arc<Ed25519Signer>,
+        address: Rc<PublicAddress>,
+    }
+
+    impl Sender {
+        fn new() -> Self {
+            let signer = Arc::new(Ed25519Signer::new_random());
+            let address = Rc::new(
+                PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!()),
+            );
+            Self { signer, address }
+        }
+
+        fn sign(&self, nonce: u64, fee: u64, timestamp: DateTime<Utc>) -> SignedTransaction {
+            let transaction = Transaction::new(
+                Rc::clone(&self.address),
+                Rc::clone(&self.address),
+                1,
+                timestamp,
+                nonce,
+            )
+            .with_fee(fee);
+            SignedTransaction::new(transaction, Arc::clone(&self.signer))
+                .unwrap_or_else(|e| panic!("sign: {e}"))
+        }
+    }
+
+    /// Ledger crediting each sender enough for a few transactions
+    fn funded(senders: &[&Sender]) -> Ledger {
+        let mut ledger = Ledger::new();
+        for sender in senders {
+            ledger
+                .credit(&sender.address, 1_000)
+                .unwrap_or_else(|e| panic!("credit: {e}"));
+        }
+        ledger
+    }
+
+    fn fees(selected: &[&SignedTransaction]) -> Vec<u64> {
+        selected
+            .iter()
+            .map(|signed| signed.get_transaction().get_fee())
+            .collect()
+    }
+
+    #[test]
+    fn test_legacy_refused() {
+        let mut mempool = Mempool::default();
+        let sender = Sender::new();
+        let ledger = funded(&[&sender]);
+        let now = Utc::now();
+
+        // fields as written before transactions were versioned
+        let mut rle = RLEByteVec::default();
+        let address = ByteVec::try_from(sender.address.as_ref())
+            .unwrap_or_else(|e| panic!("encode address: {e}"));
+        rle.add_data(Rc::new(address.clone()));
+        rle.add_data(Rc::new(address));
+        rle.add_data(Rc::new(ByteVec::new(1u64.to_le_bytes().to_vec().into())));
+        rle.add_data(Rc::new(ByteVec::new(
+            now.timestamp().to_le_bytes().to_vec().into(),
+        )));
+        let legacy = Transaction::try_from(&rle).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(legacy.get_version(), TRANSACTION_VERSION_LEGACY);
+        let signed = SignedTransaction::new(legacy, Arc::clone(&sender.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(
+            mempool.insert(signed, &ledger, now),
+            Err(MempoolError::LegacyTransaction)
+        );
+
+        // the nonce stays free for the sender's own transaction
+        assert!(mempool.insert(sender.sign(0, 0, now), &ledger, now).is_ok());
+    }
+
+    #[test]
+    fn test_multisig_recipient_refused() {
+        let mut mempool = Mempool::default();
+        let sender = Sender::new();
+        let ledger = funded(&[&sender]);
+        let now = Utc::now();
+
+        let policy = MultisigPolicy::new(1, vec![Rc::clone(&sender.address)])
+            .unwrap_or_else(|e| panic!("policy: {e}"));
+        let shared = Rc::new(policy.address().unwrap_or_else(|e| panic!("address: {e}")));
+        let transaction = Transaction::new(Rc::clone(&sender.address), shared, 1, now, 0);
+        let signed = SignedTransaction::new(transaction, Arc::clone(&sender.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(
+            mempool.insert(signed, &ledger, now),
+            Err(MempoolError::MultisigRecipient)
+        );
+        assert!(mempool.is_empty());
+    }
+
+    #[test]
+    fn test_insert_and_dedupe() {
+        let mut mempool = Mempool::default();
+        let sender = Sender::new();
+        let ledger = funded(&[&sender]);
+        let now = Utc::now();
+
+        let id = mempool
+            .insert(sender.sign(0, 1, now), &ledger, now)
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+        assert!(mempool.contains(&id));
+        assert_eq!(
+            mempool.insert(sender.sign(0, 1, now), &ledger, now),
+            Err(MempoolError::Duplicate)
+        );
+        assert_eq!(
+            mempool.insert(sender.sign(0, 1, now - Duration::seconds(5)), &ledger, now),
+            Err(MempoolError::NonceConflict(0))
+        );
+
+        // a higher fee replaces the pending transaction
+        let replacement = mempool
+            .insert(sender.sign(0, 5, now), &ledger, now)
+            .unwrap_or_else(|e| panic!("replace: {e}"));
+        assert!(!mempool.contains(&id));
+        assert!(mempool.contains(&replacement));
+        assert_eq!(mempool.len(), 1);
+
+        // signature taken from a different transaction
+        let other = sender.sign(1, 0, now);
+        let forged = SignedTransaction::from_parts(
+            Transaction::new(
+                Rc::clone(&sender.address),
+                Rc::clone(&sender.address),
+                9,
+                now,
+                1,
+            ),
+            TransactionSignature::from_parts(
+                Arc::clone(other.get_signature().get_id()),
+                Arc::clone(other.get_signature().get_signature()),
+            ),
+        );
+        assert_eq!(
+            mempool.insert(forged, &ledger, now),
+            Err(MempoolError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_select_by_fee_in_nonce_order() {
+        let mut mempool = Mempool::default();
+        let (alice, bob) = (Sender::new(), Sender::new());
+        let ledger = funded(&[&alice, &bob]);
+        let now = Utc::now();
+
+        for signed in [
+            alice.sign(1, 9, now),
+            alice.sign(0, 1, now),
+            bob.sign(0, 5, now),
+            bob.sign(1, 4, now),
+        ] {
+            mempool
+                .insert(signed, &ledger, now)
+                .unwrap_or_else(|e| panic!("insert: {e}"));
+        }
+
+        // alice's fee 9 waits behind her fee 1
+        assert_eq!(fees(&mempool.select(&ledger, 10, now)), vec![5, 4, 1, 9]);
+        assert_eq!(fees(&mempool.select(&ledger, 2, now)), vec![5, 4]);
+    }
+
+    #[test]
+    fn test_eviction_and_expiry() {
+        let mut mempool = Mempool::new(2, Duration::seconds(60));
+        let (alice, bob, carol) = (Sender::new(), Sender::new(), Sender::new());
+        let ledger = funded(&[&alice, &bob, &carol]);
+        let now = Utc::now();
+
+        assert_eq!(
+            mempool.insert(alice.sign(0, 1, now - Duration::seconds(61)), &ledger, now),
+            Err(MempoolError::Expired)
+        );
+        mempool
+            .insert(alice.sign(0, 3, now - Duration::seconds(30)), &ledger, now)
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+        mempool
+            .insert(bob.sign(0, 2, now), &ledger, now)
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+        assert_eq!(
+            mempool.insert(carol.sign(0, 1, now), &ledger, now),
+            Err(MempoolError::Full)
+        );
+        mempool
+            .insert(carol.sign(0, 4, now), &ledger, now)
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+        assert_eq!(fees(&mempool.select(&ledger, 10, now)), vec![4, 3]);
+
+        assert_eq!(mempool.expire(now + Duration::seconds(45)), 1);
+        assert_eq!(fees(&mempool.select(&ledger, 10, now)), vec![4]);
+    }
+
+    #[test]
+    fn test_admission_limits() {
+        let mut mempool = Mempool::default();
+        let (alice, broke) = (Sender::new(), Sender::new());
+        let ledger = funded(&[&alice]);
+        let now = Utc::now();
+
+        assert_eq!(
+            mempool.insert(broke.sign(0, 5, now), &ledger, now),
+            Err(MempoolError::InsufficientFunds {
+                balance: 0,
+                needed: 6
+            })
+        );
+        assert_eq!(
+            mempool.insert(alice.sign(MAX_NONCE_GAP + 1, 1, now), &ledger, now),
+            Err(MempoolError::NonceTooFar(MAX_NONCE_GAP + 1))
+        );
+        assert!(mempool
+            .insert(alice.sign(MAX_NONCE_GAP, 1, now), &ledger, now)
+            .is_ok());
+        assert_eq!(
+            mempool.insert(
+                alice.sign(0, 1, now + Duration::seconds(MAX_FUTURE_SECS + 1)),
+                &ledger,
+                now
+            ),
+            Err(MempoolError::FutureTimestamp)
+        );
+
+        // the queue as a whole must be affordable; 2 is already pending
+        assert!(mempool
+            .insert(alice.sign(0, 996, now), &ledger, now)
+            .is_ok());
+        assert_eq!(
+            mempool.insert(alice.sign(1, 1, now), &ledger, now),
+            Err(MempoolError::InsufficientFunds {
+                balance: 1_000,
+                needed: 1_001
+            })
+        );
+        // replacing a pending transaction only counts the replacement
+        assert!(mempool
+            .insert(alice.sign(0, 997, now), &ledger, now)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_remove_included() {
+        let mut mempool = Mempool::default();
+        let (alice, bob) = (Sender::new(), Sender::new());
+        let mut ledger = funded(&[&alice, &bob]);
+        let now = Utc::now();
+
+        for signed in [
+            alice.sign(0, 1, now),
+            alice.sign(1, 1, now),
+            bob.sign(0, 0, now),
+        ] {
+            mempool
+                .insert(signed, &ledger, now)
+                .unwrap_or_else(|e| panic!("insert: {e}"));
+        }
+
+        // another node's block used alice's nonce 0 for a different transaction
+        let transfer = || {
+            Transaction::new(
+                Rc::clone(&alice.address),
+                Rc::clone(&bob.address),
+                7,
+                now,
+                0,
+            )
+        };
+        let included = SignedTransaction::new(transfer(), Arc::clone(&alice.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        let block = Block::new(
+            now,
+            Block::default()
+                .hash()
+                .unwrap_or_else(|e| panic!("hash: {e}")),
+            Rc::clone(&bob.address),
+            vec![included],
+        )
+        .unwrap_or_else(|e| panic!("block: {e}"));
+        ledger
+            .apply_block(&bob.address, &[transfer()], &now)
+            .unwrap_or_else(|e| panic!("apply: {e}"));
+        mempool.remove_included(&block, &ledger);
+
+        assert_eq!(mempool.len(), 2);
+        let selected: Vec<_> = mempool
+            .select(&ledger, 10, now)
+            .iter()
+            .map(|signed| {
+                let transaction = signed.get_transaction();
+                (
+                    transaction.get_from() == &alice.address,
+                    transaction.get_nonce(),
+                )
+            })
+            .collect();
+        assert_eq!(selected, vec![(true, 1), (false, 0)]);
+        assert_eq!(
+            mempool.insert(alice.sign(0, 9, now), &ledger, now),
+            Err(MempoolError::StaleNonce(0))
+        );
+    }
+
+    #[test]
+    fn test_time_lock_and_cancel() {
+        let mut mempool = Mempool::default();
+        let (alice, bob) = (Sender::new(), Sender::new());
+        let ledger = funded(&[&alice]);
+        let now = Utc::now();
+        let due = now + Duration::days(7);
+
+        let lock = |sender: &Sender, nonce| {
+            let transaction = Transaction::new(
+                Rc::clone(&sender.address),
+                Rc::clone(&bob.address),
+                1,
+                now,
+                nonce,
+            )
+            .with_valid_after(due);
+            SignedTransaction::new(transaction, Arc::clone(&sender.signer))
+                .unwrap_or_else(|e| panic!("sign: {e}"))
+        };
+        let bounty = mempool
+            .insert(lock(&alice, 0), &ledger, now)
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+        mempool
+            .insert(alice.sign(1, 9, now), &ledger, now)
+            .unwrap_or_else(|e| panic!("insert: {e}"));
+
+        // held back with the rest of alice's queue until it matures
+        assert!(mempool.select(&ledger, 10, now).is_empty());
+        assert_eq!(fees(&mempool.select(&ledger, 10, due)), vec![0, 9]);
+        // a lock ages from when it matures
+        assert_eq!(mempool.expire(now + Duration::hours(2)), 1);
+        assert!(mempool.contains(&bounty));
+
+        // only the sender can cancel
+        let cancel = |signer: &Arc<Ed25519Signer>| {
+            let locked = mempool
+                .get(&bounty)
+                .unwrap_or_else(|| panic!("pending"))
+                .get_transaction();
+            Cancellation::new(locked, now, 0, Arc::clone(signer))
+                .unwrap_or_else(|e| panic!("cancel: {e}"))
+        };
+        let forged = cancel(&bob.signer);
+        let (cancellation, early) = (cancel(&alice.signer), cancel(&alice.signer));
+        assert_eq!(
+            mempool.cancel(forged, &ledger, now),
+            Err(MempoolError::InvalidCancellation)
+        );
+        assert_eq!(
+            mempool.cancel(cancellation, &ledger, due),
+            Err(MempoolError::Mature)
+        );
+        let replacement = mempool
+            .cancel(early, &ledger, now)
+            .unwrap_or_else(|e| panic!("cancel: {e}"));
+        assert!(!mempool.contains(&bounty));
+        assert_eq!(mempool.len(), 1);
+        assert_eq!(
+            mempool.insert(lock(&alice, 0), &ledger, now),
+            Err(MempoolError::Cancelled)
+        );
+
+        // once included, the cancellation uses up the locked transaction's nonce
+        let mut ledger = funded(&[&alice]);
+        let included = mempool
+            .get(&replacement)
+            .unwrap_or_else(|| panic!("pending"))
+            .get_transaction();
+        ledger
+            .apply(included, &now)
+            .unwrap_or_else(|e| panic!("apply: {e}"));
+        assert!(matches!(
+            ledger.apply(lock(&alice, 0).get_transaction(), &due),
+            Err(LedgerError::BadNonce { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lock_too_long() {
+        let mut mempool = Mempool::default();
+        let sender = Sender::new();
+        let ledger = funded(&[&sender]);
+        let now = Utc::now();
+
+        let lock = |seconds| {
+            let transaction = Transaction::new(
+                Rc::clone(&sender.address),
+                Rc::clone(&sender.address),
+                1,
+                now,
+                0,
+            )
+            .with_valid_after(now + Duration::seconds(seconds));
+            SignedTransaction::new(transaction, Arc::clone(&sender.signer))
+                .unwrap_or_else(|e| panic!("sign: {e}"))
+        };
+        assert_eq!(
+            mempool.insert(lock(MAX_LOCK_SECS + 1), &ledger, now),
+            Err(MempoolError::LockTooLong)
+        );
+        assert!(mempool.insert(lock(MAX_LOCK_SECS), &ledger, now).is_ok());
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::Display;
+
+/// Why the mempool refused a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    /// The signature does not verify against the sender
+    InvalidSignature,
+    /// The transaction is already pending
+    Duplicate,
+    /// Another pending transaction from the sender has this nonce and at least this fee
+    NonceConflict(u64),
+    /// The transaction uses the legacy encoding, which the ledger never applies
+    LegacyTransaction,
+    /// The nonce has already been used on chain
+    StaleNonce(u64),
+    /// The nonce is too far ahead of the sender's next nonce
+    NonceTooFar(u64),
+    /// The recipient is a shared account, which cannot spend yet
+    MultisigRecipient,
+    /// The sender's balance cannot cover this and its other pending transactions
+    InsufficientFunds {
+        /// Sender's balance on the ledger
+        balance: u64,
+        /// Amount plus fee of this and the sender's other pending transactions
+        needed: u64,
+    },
+    /// The timestamp is older than the mempool keeps
+    Expired,
+    /// The timestamp is too far in the future
+    FutureTimestamp,
+    /// The time lock is too far in the future
+    LockTooLong,
+    /// The pool is full of transactions paying higher fees
+    Full,
+    /// The transaction was cancelled by its sender
+    Cancelled,
+    /// The cancellation is not signed by the sender or does not use the nonce
+    InvalidCancellation,
+    /// No pending transaction has the id
+    UnknownTransaction,
+    /// The transaction has matured and can no longer be cancelled
+    Mature,
+    /// The transaction could not be encoded or hashed
+    Serialise(String),
+}
+
+impl Display for MempoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "Invalid transaction signature"),
+            Self::Duplicate => write!(f, "Transaction already pending"),
+            Self::NonceConflict(nonce) => {
+                write!(f, "A pending transaction already uses nonce {nonce}")
+            }
+            Self::LegacyTransaction => write!(f, "Legacy transactions cannot be applied"),
+            Self::StaleNonce(nonce) => write!(f, "Nonce {nonce} has already been used"),
+            Self::NonceTooFar(nonce) => write!(f, "Nonce {nonce} is too far ahead"),
+            Self::MultisigRecipient => write!(f, "Shared accounts cannot receive funds yet"),
+            Self::InsufficientFunds { balance, needed } => {
+                write!(f, "Balance of {balance} cannot cover {needed}")
+            }
+            Self::Expired => write!(f, "Transaction timestamp has expired"),
+            Self::FutureTimestamp => write!(f, "Transaction timestamp is in the future"),
+            Self::LockTooLong => write!(f, "Transaction is locked for too long"),
+            Self::Full => write!(f, "Mempool is full"),
+            Self::Cancelled => write!(f, "Transaction was cancelled by its sender"),
+            Self::InvalidCancellation => write!(f, "Cancellation does not match the transaction"),
+            Self::UnknownTransaction => write!(f, "Transaction is not pending"),
+            Self::Mature => write!(f, "Transaction has matured and cannot be cancelled"),
+            Self::Serialise(reason) => write!(f, "Transaction could not be encoded: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for MempoolError {}
+
+impl From<base_xx::SerialiseError> for MempoolError {
+    fn from(value: base_xx::SerialiseError) -> Self {
+        Self::Serialise(value.to_string())
+    }
+}
@@ -0,0 +1,9 @@
+/// Pending transaction pool
+#[allow(clippy::module_inception)]
+pub mod mempool;
+
+/// Mempool errors
+pub mod mempool_error;
+
+pub use mempool::Mempool;
+pub use mempool_error::MempoolError;
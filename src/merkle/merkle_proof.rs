@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use slahasher::Hash;
+
+use crate::merkle::merkle_tree::{hash_leaf, hash_node};
+use crate::serialise::{RleDecode, RleEncode};
+
+/// Proof that a transaction id is a leaf of a `MerkleTree`
+///
+/// Holds the sibling on the path from the leaf to the root at every level
+/// where there is one. Encodes as RLE fields `[index][leaf_count][siblings]`.
+///
+/// The root does not commit to the number of leaves, so `index` and
+/// `leaf_count` only shape the path: a proof shows that a transaction is in
+/// the tree, not where. The same path can be restated for another position in
+/// a tree of another size and still verify.
+#[derive(Debug, PartialEq, Eq, RleEncode, RleDecode)]
+pub struct MerkleProof {
+    index: u64,
+    leaf_count: u64,
+    siblings: Vec<Arc<Hash>>,
+}
+
+impl MerkleProof {
+    /// Create a proof for leaf `index` of a tree with `leaf_count` leaves
+    #[must_use]
+    pub const fn new(index: u64, leaf_count: u64, siblings: Vec<Arc<Hash>>) -> Self {
+        Self {
+            index,
+            leaf_count,
+            siblings,
+        }
+    }
+
+    /// Get position of the leaf, as claimed by the prover
+    #[must_use]
+    pub const fn get_index(&self) -> u64 {
+        self.index
+    }
+
+    /// Get number of leaves in the tree, as claimed by the prover
+    #[must_use]
+    pub const fn get_leaf_count(&self) -> u64 {
+        self.leaf_count
+    }
+
+    /// Get siblings from the leaf upwards
+    #[must_use]
+    pub fn get_siblings(&self) -> &[Arc<Hash>] {
+        &self.siblings
+    }
+
+    /// Check that `id` is a leaf of the tree with `root`
+    ///
+    /// Only inclusion is proved; see the type documentation for why the
+    /// position is not.
+    #[must_use]
+    pub fn verify(&self, id: &Hash, root: &Hash) -> bool {
+        if self.index >= self.leaf_count {
+            return false;
+        }
+        let Ok(mut node) = hash_leaf(id) else {
+            return false;
+        };
+        let mut siblings = self.siblings.iter();
+        let mut position = self.index;
+        let mut width = self.leaf_count;
+        while width > 1 {
+            // the last node of an odd level is promoted without a sibling
+            let promoted = position == width - 1 && !width.is_multiple_of(2);
+            if !promoted {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                let parent = if position.is_multiple_of(2) {
+                    hash_node(&node, sibling)
+                } else {
+                    hash_node(sibling, &node)
+                };
+                let Ok(parent) = parent else {
+                    return false;
+                };
+                node = parent;
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        siblings.next().is_none() && *node == *root
+    }
+}
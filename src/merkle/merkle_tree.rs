@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use base_xx::{ByteVec, SerialiseError};
+use slahasher::{Hash, HashAlgorithm};
+
+use crate::merkle::MerkleProof;
+
+/// Prefix of hashed leaves, so a leaf can never be passed off as a node
+const LEAF_PREFIX: u8 = 0;
+
+/// Prefix of hashed internal nodes
+const NODE_PREFIX: u8 = 1;
+
+fn keccak(parts: &[&[u8]]) -> Result<Arc<Hash>, SerialiseError> {
+    let bytes: Vec<u8> = parts.concat();
+    Hash::try_hash(
+        Arc::new(ByteVec::new(bytes.into())),
+        HashAlgorithm::KECCAK512,
+    )
+}
+
+/// Hash of a leaf holding `id`
+pub(crate) fn hash_leaf(id: &Hash) -> Result<Arc<Hash>, SerialiseError> {
+    keccak(&[&[LEAF_PREFIX], id.get_bytes().get_bytes()])
+}
+
+/// Hash of the node above `left` and `right`
+pub(crate) fn hash_node(left: &Hash, right: &Hash) -> Result<Arc<Hash>, SerialiseError> {
+    keccak(&[
+        &[NODE_PREFIX],
+        left.get_bytes().get_bytes(),
+        right.get_bytes().get_bytes(),
+    ])
+}
+
+/// KECCAK512 Merkle tree over transaction ids
+///
+/// Leaves and nodes are hashed with distinct prefixes. A level with an odd
+/// number of nodes promotes its last node unchanged rather than pairing it
+/// with itself, so two different id lists never share a root.
+#[derive(Debug)]
+pub struct MerkleTree {
+    /// Leaf hashes first, the root alone last
+    levels: Vec<Vec<Arc<Hash>>>,
+}
+
+impl MerkleTree {
+    /// Build the tree over `ids` in order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if hashing fails
+    pub fn new(ids: &[Arc<Hash>]) -> Result<Self, SerialiseError> {
+        let leaves = ids
+            .iter()
+            .map(|id| hash_leaf(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut levels = vec![leaves];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let level = levels.last().map_or(&[][..], Vec::as_slice);
+            let next = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(left, right),
+                    _ => Ok(Arc::clone(&pair[0])),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            levels.push(next);
+        }
+        Ok(Self { levels })
+    }
+
+    /// Number of leaves
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    /// Whether the tree has no leaves
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Root hash, the KECCAK512 of no bytes for an empty tree
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tree is empty and hashing fails
+    pub fn root(&self) -> Result<Arc<Hash>, SerialiseError> {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .map_or_else(|| keccak(&[]), |root| Ok(Arc::clone(root)))
+    }
+
+    /// Proof that the leaf at `index` is in the tree
+    #[must_use]
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(Arc::clone(sibling));
+            }
+            position /= 2;
+        }
+        Some(MerkleProof::new(index as u64, self.len() as u64, siblings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: u8) -> Vec<Arc<Hash>> {
+        (0..count)
+            .map(|i| {
+                Hash::try_hash(
+                    Arc::new(ByteVec::new(vec![i].into())),
+                    HashAlgorithm::KECCAK512,
+                )
+                .unwrap_or_else(|e| panic!("hash: {e}"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_root() {
+        let empty = MerkleTree::new(&[]).unwrap_or_else(|e| panic!("tree: {e}"));
+        assert!(empty.is_empty());
+        assert!(empty.proof(0).is_none());
+
+        let ids = ids(3);
+        let tree = MerkleTree::new(&ids).unwrap_or_else(|e| panic!("tree: {e}"));
+        let leaf = |i: usize| hash_leaf(&ids[i]).unwrap_or_else(|e| panic!("leaf: {e}"));
+        let left = hash_node(&leaf(0), &leaf(1)).unwrap_or_else(|e| panic!("node: {e}"));
+        let expected = hash_node(&left, &leaf(2)).unwrap_or_else(|e| panic!("node: {e}"));
+        assert_eq!(tree.root().ok(), Some(expected));
+
+        // a single leaf is still hashed, so the root is never a bare id
+        let single = MerkleTree::new(&ids[..1]).unwrap_or_else(|e| panic!("tree: {e}"));
+        assert_eq!(single.root().ok(), Some(leaf(0)));
+        assert_ne!(single.root().ok(), Some(Arc::clone(&ids[0])));
+
+        // repeating the odd leaf gives a different root
+        let mut padded = ids.clone();
+        padded.push(Arc::clone(&ids[2]));
+        let padded = MerkleTree::new(&padded).unwrap_or_else(|e| panic!("tree: {e}"));
+        assert_ne!(padded.root().ok(), tree.root().ok());
+    }
+
+    #[test]
+    fn test_proofs() {
+        for count in 1..=9 {
+            let ids = ids(count);
+            let tree = MerkleTree::new(&ids).unwrap_or_else(|e| panic!("tree: {e}"));
+            let root = tree.root().unwrap_or_else(|e| panic!("root: {e}"));
+            for (index, id) in ids.iter().enumerate() {
+                let proof = tree
+                    .proof(index)
+                    .unwrap_or_else(|| panic!("proof {index} of {count}"));
+                assert!(proof.verify(id, &root), "leaf {index} of {count}");
+                let other = &ids[(index + 1) % ids.len()];
+                if other != id {
+                    assert!(!proof.verify(other, &root));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+/// Inclusion proofs
+pub mod merkle_proof;
+
+/// Tree over transaction ids
+pub mod merkle_tree;
+
+pub use merkle_proof::MerkleProof;
+pub use merkle_tree::MerkleTree;
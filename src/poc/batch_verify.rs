@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use chrono::Utc;
+    use simple_sign::Ed25519Signer;
+
+    use crate::address::public_address::PublicAddress;
+    use crate::transactions::batch_verify::verify_batch;
+    use crate::transactions::{SignedTransaction, Transaction};
+
+    /// Compares verifying a block's worth of transactions one at a time with
+    /// `verify_batch`
+    ///
+    /// Timing depends on the machine and core count, so run it on demand with
+    /// `cargo test --release -- --ignored --nocapture test_batch_verify_throughput`.
+    #[test]
+    #[ignore = "timing benchmark"]
+    fn test_batch_verify_throughput() {
+        let signer = Arc::new(Ed25519Signer::new_random());
+        let from = Rc::new(
+            PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|e| unreachable!("broke {e}")),
+        );
+        let now = Utc::now();
+        let transactions: Vec<SignedTransaction> = (0..500)
+            .map(|nonce| {
+                let transaction =
+                    Transaction::new(Rc::clone(&from), Rc::clone(&from), 1, now, nonce);
+                SignedTransaction::new(transaction, Arc::clone(&signer))
+                    .unwrap_or_else(|e| unreachable!("broke {e}"))
+            })
+            .collect();
+
+        let start = Instant::now();
+        let serial_failed = transactions
+            .iter()
+            .filter(|signed| !signed.verify())
+            .count();
+        let serial = start.elapsed();
+
+        let start = Instant::now();
+        let batch_failed = verify_batch(&transactions).len();
+        let batch = start.elapsed();
+
+        println!(
+            "Verified {} transactions: one at a time {serial:?}, batched {batch:?}",
+            transactions.len()
+        );
+        assert_eq!(serial_failed, 0);
+        assert_eq!(batch_failed, 0);
+    }
+}
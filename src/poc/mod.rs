@@ -1,6 +1,9 @@
 /// Asynchronous testing examples.
 pub mod async_testing;
 
+/// Batch signature verification benchmark
+pub mod batch_verify;
+
 ///// Networking examples and tests.
 //pub mod networking;
 
@@ -0,0 +1,79 @@
+use base_xx::SerialiseError;
+
+/// Mask selecting the type tag bits of an RLE prefix byte
+pub const FIELD_TYPE_MASK: u8 = 0b0001_1111;
+
+/// Kind of value held in an `RLEByteVec` field
+///
+/// Stored in the low 5 bits of each field's prefix byte, so a payload can be
+/// walked and pretty-printed without knowing its schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FieldType {
+    /// Opaque bytes
+    Bytes,
+    /// Unsigned 64 bit integer, little-endian
+    U64,
+    /// Signed 64 bit integer, little-endian
+    I64,
+    /// UTF-8 string
+    Utf8,
+    /// Nested `RLEByteVec`
+    Rle,
+    /// `slahasher::Hash` in its `[algorithm][digest...]` form
+    Hash,
+    /// `simple_sign::Signature` in its `[algorithm][signature...]` form
+    Signature,
+}
+
+impl FieldType {
+    /// Name used when pretty-printing a field
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Bytes => "bytes",
+            Self::U64 => "u64",
+            Self::I64 => "i64",
+            Self::Utf8 => "utf8",
+            Self::Rle => "rle",
+            Self::Hash => "hash",
+            Self::Signature => "signature",
+        }
+    }
+}
+
+impl TryFrom<u8> for FieldType {
+    type Error = SerialiseError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Bytes),
+            1 => Ok(Self::U64),
+            2 => Ok(Self::I64),
+            3 => Ok(Self::Utf8),
+            4 => Ok(Self::Rle),
+            5 => Ok(Self::Hash),
+            6 => Ok(Self::Signature),
+            _ => Err(SerialiseError::new(format!("Unknown field type {value}"))),
+        }
+    }
+}
+
+impl From<FieldType> for u8 {
+    fn from(value: FieldType) -> Self {
+        match value {
+            FieldType::Bytes => 0,
+            FieldType::U64 => 1,
+            FieldType::I64 => 2,
+            FieldType::Utf8 => 3,
+            FieldType::Rle => 4,
+            FieldType::Hash => 5,
+            FieldType::Signature => 6,
+        }
+    }
+}
+
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -1,4 +1,24 @@
+/// Field type tags
+pub mod field_type;
+
 /// Serialization system
 pub mod rle_bytevec;
 
+/// Borrowed view for decoding without copying
+pub mod rle_bytevec_ref;
+
+/// Traits for struct encoding, usually derived
+pub mod rle_codec;
+
+/// Streaming decoder
+pub mod rle_reader;
+
+pub use field_type::FieldType;
 pub use rle_bytevec::RLEByteVec;
+pub use rle_bytevec_ref::{RLEByteVecRef, RLEFieldIter};
+pub use rle_codec::{RleDecode, RleDecodeField, RleEncode, RleEncodeField};
+pub use rle_reader::{RLELimits, RLEReader};
+pub use subversive_derive::{RleDecode, RleEncode};
+
+// named by the derive macros, so users of the derives need not depend on base_xx
+pub use base_xx::SerialiseError;
@@ -1,26 +1,39 @@
 use std::rc::Rc;
 
 use base_xx::{ByteVec, SerialiseError};
+use simple_sign::Signature;
+use slahasher::Hash;
+use std::sync::Arc;
+
+use crate::serialise::field_type::{FieldType, FIELD_TYPE_MASK};
+use crate::serialise::RLEByteVecRef;
 
 /// Run-length encoded byte vector
 /// can hold multiple byte vecs with a header indicating the length of each vec
+///
+/// Each field is prefixed by a byte whose top 3 bits hold the number of length
+/// bytes that follow (minus one) and whose low 5 bits hold the `FieldType`.
 pub struct RLEByteVec {
     data: Vec<Rc<ByteVec>>,
+    types: Vec<FieldType>,
 }
 
-fn encode_len(len: usize) -> Result<Vec<u8>, SerialiseError> {
+/// Smallest number of length bytes that can hold `len`
+pub(crate) const fn len_bytes_needed(len: u64) -> usize {
+    let bits = 64u32.saturating_sub(len.leading_zeros());
+    let bytes = bits.div_ceil(8) as usize;
+    if bytes == 0 {
+        1
+    } else {
+        bytes
+    }
+}
+
+fn encode_len(len: usize, field_type: FieldType) -> Result<Vec<u8>, SerialiseError> {
     let len_u64 =
         u64::try_from(len).map_err(|_| SerialiseError::new("Length too large".to_string()))?;
 
-    let needed_bytes = {
-        let bits = 64u32.saturating_sub(len_u64.leading_zeros());
-        let bytes = bits.div_ceil(8) as usize;
-        if bytes == 0 {
-            1
-        } else {
-            bytes
-        }
-    };
+    let needed_bytes = len_bytes_needed(len_u64);
 
     if needed_bytes > 8 {
         return Err(SerialiseError::new("Length too large".to_string()));
@@ -29,17 +42,33 @@ fn encode_len(len: usize) -> Result<Vec<u8>, SerialiseError> {
     let mut out = Vec::with_capacity(1 + needed_bytes);
     let prefix_bits = u8::try_from(needed_bytes - 1)
         .map_err(|_| SerialiseError::new("Length too large".to_string()))?;
-    let prefix = prefix_bits << 5;
+    let prefix = (prefix_bits << 5) | u8::from(field_type);
     out.push(prefix);
     out.extend_from_slice(&len_u64.to_le_bytes()[..needed_bytes]);
     Ok(out)
 }
 
-fn decode_len(bytes: &[u8], offset: usize) -> Result<(u64, usize), SerialiseError> {
+/// Split a prefix byte into its field type and the number of length bytes that follow
+pub(crate) fn decode_prefix(prefix: u8) -> Result<(FieldType, usize), SerialiseError> {
+    let field_type = FieldType::try_from(prefix & FIELD_TYPE_MASK)?;
+    Ok((field_type, ((prefix >> 5) as usize) + 1))
+}
+
+/// Read a little-endian length of up to 8 bytes
+pub(crate) fn len_from_le(slice: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf[..slice.len()].copy_from_slice(slice);
+    u64::from_le_bytes(buf)
+}
+
+pub(crate) fn decode_len(
+    bytes: &[u8],
+    offset: usize,
+) -> Result<(FieldType, u64, usize), SerialiseError> {
     let prefix = *bytes
         .get(offset)
         .ok_or_else(|| SerialiseError::new("Unexpected end of input".to_string()))?;
-    let len_bytes = ((prefix >> 5) as usize) + 1;
+    let (field_type, len_bytes) = decode_prefix(prefix)?;
     let start = offset + 1;
     let end = start + len_bytes;
 
@@ -47,17 +76,18 @@ fn decode_len(bytes: &[u8], offset: usize) -> Result<(u64, usize), SerialiseErro
         .get(start..end)
         .ok_or_else(|| SerialiseError::new("Unexpected end of input".to_string()))?;
 
-    let mut buf = [0u8; 8];
-    buf[..len_bytes].copy_from_slice(slice);
-    Ok((u64::from_le_bytes(buf), 1 + len_bytes))
+    Ok((field_type, len_from_le(slice), 1 + len_bytes))
 }
 
 impl RLEByteVec {
     /// Create a new RLEByteVec
+    ///
+    /// Every field is tagged as `FieldType::Bytes`.
     #[must_use]
     #[allow(clippy::doc_markdown)]
-    pub const fn new(data: Vec<Rc<ByteVec>>) -> Self {
-        Self { data }
+    pub fn new(data: Vec<Rc<ByteVec>>) -> Self {
+        let types = vec![FieldType::Bytes; data.len()];
+        Self { data, types }
     }
 
     /// Get the data
@@ -67,11 +97,237 @@ impl RLEByteVec {
         &self.data
     }
 
+    /// Get the type tag of each field
+    #[must_use]
+    pub const fn get_types(&self) -> &Vec<FieldType> {
+        &self.types
+    }
+
+    /// Get the type tag of the field at `index`
+    #[must_use]
+    pub fn get_field_type(&self, index: usize) -> Option<FieldType> {
+        self.types.get(index).copied()
+    }
+
     /// Add data to the RLEByteVec
     #[allow(clippy::doc_markdown)]
     pub fn add_data(&mut self, data: Rc<ByteVec>) {
+        self.add_field(FieldType::Bytes, data);
+    }
+
+    /// Add a field with an explicit type tag
+    pub fn add_field(&mut self, field_type: FieldType, data: Rc<ByteVec>) {
         self.data.push(data);
+        self.types.push(field_type);
+    }
+
+    /// Add a `u64` field
+    pub fn add_u64(&mut self, value: u64) {
+        self.add_field(
+            FieldType::U64,
+            Rc::new(ByteVec::new(value.to_le_bytes().to_vec().into())),
+        );
+    }
+
+    /// Add an `i64` field
+    pub fn add_i64(&mut self, value: i64) {
+        self.add_field(
+            FieldType::I64,
+            Rc::new(ByteVec::new(value.to_le_bytes().to_vec().into())),
+        );
+    }
+
+    /// Add a UTF-8 string field
+    pub fn add_string(&mut self, value: &str) {
+        self.add_field(
+            FieldType::Utf8,
+            Rc::new(ByteVec::new(value.as_bytes().to_vec().into())),
+        );
+    }
+
+    /// Add a nested RLEByteVec field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the nested value cannot be encoded
+    #[allow(clippy::doc_markdown)]
+    pub fn add_rle(&mut self, value: &Self) -> Result<(), SerialiseError> {
+        let bytes = ByteVec::try_from(value)?;
+        self.add_field(FieldType::Rle, Rc::new(bytes));
+        Ok(())
+    }
+
+    /// Add a hash field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hash cannot be converted to bytes
+    pub fn add_hash(&mut self, value: &Hash) -> Result<(), SerialiseError> {
+        let bytes = value.try_to_byte_vec()?;
+        self.add_field(FieldType::Hash, Rc::new((*bytes).clone()));
+        Ok(())
+    }
+
+    /// Add a signature field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature cannot be converted to bytes
+    pub fn add_signature(&mut self, value: &Signature) -> Result<(), SerialiseError> {
+        let mut bytes = vec![u8::from(value.get_algorithm())];
+        bytes.extend_from_slice(value.get_signature().get_bytes());
+        self.add_field(FieldType::Signature, Rc::new(ByteVec::new(bytes.into())));
+        Ok(())
+    }
+
+    /// Get the bytes of the field at `index`, checking its type tag
+    ///
+    /// `name` is used in error messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing or tagged with a different type
+    pub fn get_typed(
+        &self,
+        index: usize,
+        expected: FieldType,
+        name: &str,
+    ) -> Result<&[u8], SerialiseError> {
+        let data = self
+            .data
+            .get(index)
+            .ok_or_else(|| SerialiseError::new(format!("Missing {name} field")))?;
+        let field_type = self.types.get(index).copied().unwrap_or(FieldType::Bytes);
+        check_field_type(field_type, expected, name)?;
+        Ok(data.get_bytes())
+    }
+
+    /// Get a `u64` field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or not 8 bytes
+    pub fn get_u64(&self, index: usize, name: &str) -> Result<u64, SerialiseError> {
+        let bytes = self.get_typed(index, FieldType::U64, name)?;
+        Ok(u64::from_le_bytes(fixed_bytes(bytes, name)?))
+    }
+
+    /// Get an `i64` field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or not 8 bytes
+    pub fn get_i64(&self, index: usize, name: &str) -> Result<i64, SerialiseError> {
+        let bytes = self.get_typed(index, FieldType::I64, name)?;
+        Ok(i64::from_le_bytes(fixed_bytes(bytes, name)?))
+    }
+
+    /// Get a UTF-8 string field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or not valid UTF-8
+    pub fn get_string(&self, index: usize, name: &str) -> Result<String, SerialiseError> {
+        let bytes = self.get_typed(index, FieldType::Utf8, name)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| SerialiseError::new(format!("Field {name} is not valid UTF-8")))
     }
+
+    /// Get a nested RLEByteVec field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or cannot be decoded
+    #[allow(clippy::doc_markdown)]
+    pub fn get_rle(&self, index: usize, name: &str) -> Result<Self, SerialiseError> {
+        let bytes = self.get_typed(index, FieldType::Rle, name)?;
+        Self::try_from(&ByteVec::new(bytes.to_vec().into()))
+    }
+
+    /// Get a hash field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or cannot be decoded
+    pub fn get_hash(&self, index: usize, name: &str) -> Result<Hash, SerialiseError> {
+        let bytes = self.get_typed(index, FieldType::Hash, name)?;
+        if bytes.is_empty() {
+            return Err(SerialiseError::new(format!("Field {name} is empty")));
+        }
+        Hash::try_from(Arc::new(ByteVec::new(bytes.to_vec().into())))
+    }
+
+    /// Get a signature field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or cannot be decoded
+    pub fn get_signature(&self, index: usize, name: &str) -> Result<Signature, SerialiseError> {
+        let bytes = self.get_typed(index, FieldType::Signature, name)?;
+        if bytes.is_empty() {
+            return Err(SerialiseError::new(format!("Field {name} is empty")));
+        }
+        Signature::try_from(Arc::new(ByteVec::new(bytes.to_vec().into())))
+    }
+
+    fn fmt_indented(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        for (index, (data, field_type)) in self.data.iter().zip(&self.types).enumerate() {
+            let bytes = data.get_bytes();
+            write!(f, "{indent}[{index}] {field_type}: ")?;
+            match field_type {
+                FieldType::U64 => match <[u8; 8]>::try_from(bytes) {
+                    Ok(b) => writeln!(f, "{}", u64::from_le_bytes(b))?,
+                    Err(_) => writeln!(f, "<invalid {} bytes>", bytes.len())?,
+                },
+                FieldType::I64 => match <[u8; 8]>::try_from(bytes) {
+                    Ok(b) => writeln!(f, "{}", i64::from_le_bytes(b))?,
+                    Err(_) => writeln!(f, "<invalid {} bytes>", bytes.len())?,
+                },
+                FieldType::Utf8 => writeln!(f, "{:?}", String::from_utf8_lossy(bytes))?,
+                FieldType::Rle => match Self::try_from(&**data) {
+                    Ok(nested) => {
+                        writeln!(f)?;
+                        nested.fmt_indented(f, depth + 1)?;
+                    }
+                    Err(e) => writeln!(f, "<invalid: {e}>")?,
+                },
+                FieldType::Bytes | FieldType::Hash | FieldType::Signature => {
+                    writeln!(f, "{}", to_hex(bytes))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check a field's type tag against the one expected for `name`
+pub(crate) fn check_field_type(
+    field_type: FieldType,
+    expected: FieldType,
+    name: &str,
+) -> Result<(), SerialiseError> {
+    if field_type != expected {
+        return Err(SerialiseError::new(format!(
+            "Field {name} must be {expected}, found {field_type}"
+        )));
+    }
+    Ok(())
+}
+
+/// Interpret a field as exactly 8 bytes
+pub(crate) fn fixed_bytes(bytes: &[u8], name: &str) -> Result<[u8; 8], SerialiseError> {
+    bytes
+        .try_into()
+        .map_err(|_| SerialiseError::new(format!("Field {name} must be 8 bytes")))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
 }
 
 impl Default for RLEByteVec {
@@ -80,15 +336,22 @@ impl Default for RLEByteVec {
     }
 }
 
+impl std::fmt::Display for RLEByteVec {
+    /// Pretty-print every field using its type tag
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
 impl TryFrom<&RLEByteVec> for ByteVec {
     type Error = SerialiseError;
 
     fn try_from(value: &RLEByteVec) -> Result<Self, Self::Error> {
         let mut result: Vec<u8> = vec![];
-        for data in value.get_data() {
+        for (data, field_type) in value.get_data().iter().zip(value.get_types()) {
             let bytes = data.get_bytes();
             let len = bytes.len();
-            result.extend_from_slice(&encode_len(len)?);
+            result.extend_from_slice(&encode_len(len, *field_type)?);
             result.extend_from_slice(bytes);
         }
         Ok(Self::new(result.into()))
@@ -99,29 +362,12 @@ impl TryFrom<&ByteVec> for RLEByteVec {
     type Error = SerialiseError;
 
     fn try_from(value: &ByteVec) -> Result<Self, Self::Error> {
-        let mut out: Vec<Rc<ByteVec>> = Vec::new();
-        let bytes = value.get_bytes();
-
-        let mut offset = 0usize;
-        while offset < bytes.len() {
-            let (len_u64, consumed) = decode_len(bytes, offset)?;
-            offset += consumed;
-
-            let len = usize::try_from(len_u64)
-                .map_err(|_| SerialiseError::new("Length too large".to_string()))?;
-
-            let end = offset
-                .checked_add(len)
-                .ok_or_else(|| SerialiseError::new("Length too large".to_string()))?;
-            let slice = bytes
-                .get(offset..end)
-                .ok_or_else(|| SerialiseError::new("Unexpected end of input".to_string()))?;
-
-            out.push(Rc::new(ByteVec::new(slice.to_vec().into())));
-            offset = end;
+        let mut out = Self::default();
+        for field in RLEByteVecRef::new(value.get_bytes()) {
+            let (field_type, slice) = field?;
+            out.add_field(field_type, Rc::new(ByteVec::new(slice.to_vec().into())));
         }
-
-        Ok(Self::new(out))
+        Ok(out)
     }
 }
 
@@ -177,15 +423,78 @@ mod tests {
             let Ok(len_usize) = usize::try_from(len) else {
                 panic!("u64 length does not fit in usize");
             };
-            let Ok(enc) = encode_len(len_usize) else {
+            let Ok(enc) = encode_len(len_usize, FieldType::Utf8) else {
                 panic!("encode length failed");
             };
             assert_eq!(((enc[0] >> 5) as usize) + 1, expected_bytes);
-            let Ok((dec, consumed)) = decode_len(&enc, 0) else {
+            let Ok((field_type, dec, consumed)) = decode_len(&enc, 0) else {
                 panic!("decode length failed");
             };
+            assert_eq!(field_type, FieldType::Utf8);
             assert_eq!(consumed, 1 + expected_bytes);
             assert_eq!(dec, len);
         }
     }
+
+    #[test]
+    fn test_typed_fields() {
+        let hash = Hash::try_hash(
+            Arc::new(ByteVec::new(vec![1, 2, 3].into())),
+            slahasher::HashAlgorithm::KECCAK512,
+        )
+        .unwrap_or_else(|e| unreachable!("hash {e}"));
+
+        let mut nested = RLEByteVec::default();
+        nested.add_string("inner");
+
+        let mut rle = RLEByteVec::default();
+        rle.add_u64(42);
+        rle.add_i64(-7);
+        rle.add_string("hello");
+        rle.add_rle(&nested)
+            .unwrap_or_else(|e| panic!("add nested: {e}"));
+        rle.add_hash(&hash)
+            .unwrap_or_else(|e| panic!("add hash: {e}"));
+        rle.add_signature(&Signature::default())
+            .unwrap_or_else(|e| panic!("add signature: {e}"));
+
+        let encoded = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = RLEByteVec::try_from(&encoded).unwrap_or_else(|e| panic!("decode: {e}"));
+
+        assert_eq!(decoded.get_types(), rle.get_types());
+        assert_eq!(decoded.get_u64(0, "a").ok(), Some(42));
+        assert_eq!(decoded.get_i64(1, "b").ok(), Some(-7));
+        assert_eq!(decoded.get_string(2, "c").ok().as_deref(), Some("hello"));
+        let inner = decoded
+            .get_rle(3, "d")
+            .unwrap_or_else(|e| panic!("nested: {e}"));
+        assert_eq!(inner.get_string(0, "e").ok().as_deref(), Some("inner"));
+        assert_eq!(decoded.get_hash(4, "f").ok().as_ref(), Some(&*hash));
+        assert_eq!(
+            decoded.get_signature(5, "g").ok(),
+            Some(Signature::default())
+        );
+
+        let printed = decoded.to_string();
+        slogger::debug!("{printed}");
+        assert!(printed.contains("[0] u64: 42"));
+        assert!(printed.contains("[2] utf8: \"hello\""));
+        assert!(printed.contains("  [0] utf8: \"inner\""));
+    }
+
+    #[test]
+    fn test_wrong_field_type_rejected() {
+        let mut rle = RLEByteVec::default();
+        rle.add_string("not a number");
+
+        let err = rle.get_u64(0, "amount").err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Field amount must be u64, found utf8"));
+        assert!(rle.get_u64(1, "amount").is_err());
+    }
+
+    #[test]
+    fn test_unknown_field_type_rejected() {
+        let encoded = ByteVec::new(vec![0b0000_0111, 0].into());
+        assert!(RLEByteVec::try_from(&encoded).is_err());
+    }
 }
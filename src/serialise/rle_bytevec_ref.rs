@@ -0,0 +1,337 @@
+use base_xx::SerialiseError;
+
+use crate::serialise::rle_bytevec::{check_field_type, decode_len, fixed_bytes, len_bytes_needed};
+use crate::serialise::FieldType;
+
+/// Borrowed view over an encoded `RLEByteVec`
+///
+/// Fields are handed out as slices of the original buffer, so decoding a
+/// payload does not copy each field into its own `ByteVec`.
+///
+/// A canonical view additionally rejects length prefixes that use more bytes
+/// than needed, and `RLEFieldIter::finish` rejects unread trailing fields, so
+/// only one byte string decodes to a given value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RLEByteVecRef<'a> {
+    bytes: &'a [u8],
+    canonical: bool,
+}
+
+impl<'a> RLEByteVecRef<'a> {
+    /// Create a view over encoded bytes
+    #[must_use]
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            canonical: false,
+        }
+    }
+
+    /// Create a view that only accepts the canonical encoding
+    #[must_use]
+    pub const fn canonical(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            canonical: true,
+        }
+    }
+
+    /// Whether this view only accepts the canonical encoding
+    #[must_use]
+    pub const fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Get the encoded bytes
+    #[must_use]
+    pub const fn get_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Iterate over the fields in order
+    #[must_use]
+    pub const fn iter(&self) -> RLEFieldIter<'a> {
+        RLEFieldIter {
+            bytes: self.bytes,
+            offset: 0,
+            canonical: self.canonical,
+        }
+    }
+
+    /// Get the field at `index`, checking its type tag
+    ///
+    /// Walks the fields from the start; use `iter` to read several fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload is malformed, the field is missing or
+    /// it is tagged with a different type
+    pub fn get_typed(
+        &self,
+        index: usize,
+        expected: FieldType,
+        name: &str,
+    ) -> Result<&'a [u8], SerialiseError> {
+        let mut fields = self.iter();
+        for _ in 0..index {
+            fields
+                .next()
+                .transpose()?
+                .ok_or_else(|| SerialiseError::new(format!("Missing {name} field")))?;
+        }
+        fields.next_typed(expected, name)
+    }
+}
+
+impl<'a> IntoIterator for RLEByteVecRef<'a> {
+    type Item = Result<(FieldType, &'a [u8]), SerialiseError>;
+    type IntoIter = RLEFieldIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &RLEByteVecRef<'a> {
+    type Item = Result<(FieldType, &'a [u8]), SerialiseError>;
+    type IntoIter = RLEFieldIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the fields of an `RLEByteVecRef`
+#[derive(Debug, Clone)]
+pub struct RLEFieldIter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    canonical: bool,
+}
+
+impl<'a> RLEFieldIter<'a> {
+    /// Whether every field has been read
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    /// Type of the next field without reading it
+    ///
+    /// Returns `None` at the end of the payload or if the next field is malformed.
+    #[must_use]
+    pub fn peek_type(&self) -> Option<FieldType> {
+        self.clone()
+            .next()
+            .and_then(Result::ok)
+            .map(|(field_type, _)| field_type)
+    }
+
+    /// Check that every field of a canonical payload has been read
+    ///
+    /// Lenient iterators accept trailing fields so newer encodings can add them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the iterator is canonical and fields remain
+    pub fn finish(&self, name: &str) -> Result<(), SerialiseError> {
+        if self.canonical && !self.is_finished() {
+            return Err(SerialiseError::new(format!(
+                "Unexpected trailing fields in {name}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read the next field, checking its type tag
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload is malformed, the field is missing or
+    /// it is tagged with a different type
+    pub fn next_typed(
+        &mut self,
+        expected: FieldType,
+        name: &str,
+    ) -> Result<&'a [u8], SerialiseError> {
+        let (field_type, slice) = self
+            .next()
+            .transpose()?
+            .ok_or_else(|| SerialiseError::new(format!("Missing {name} field")))?;
+        check_field_type(field_type, expected, name)?;
+        Ok(slice)
+    }
+
+    /// Read the next field as a `u64`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or not 8 bytes
+    pub fn next_u64(&mut self, name: &str) -> Result<u64, SerialiseError> {
+        let bytes = self.next_typed(FieldType::U64, name)?;
+        Ok(u64::from_le_bytes(fixed_bytes(bytes, name)?))
+    }
+
+    /// Read the next field as an `i64`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or not 8 bytes
+    pub fn next_i64(&mut self, name: &str) -> Result<i64, SerialiseError> {
+        let bytes = self.next_typed(FieldType::I64, name)?;
+        Ok(i64::from_le_bytes(fixed_bytes(bytes, name)?))
+    }
+
+    /// Read the next field as a UTF-8 string
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of another type or not valid UTF-8
+    pub fn next_str(&mut self, name: &str) -> Result<&'a str, SerialiseError> {
+        let bytes = self.next_typed(FieldType::Utf8, name)?;
+        std::str::from_utf8(bytes)
+            .map_err(|_| SerialiseError::new(format!("Field {name} is not valid UTF-8")))
+    }
+
+    /// Read the next field as a nested view
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing or of another type
+    pub fn next_rle(&mut self, name: &str) -> Result<RLEByteVecRef<'a>, SerialiseError> {
+        Ok(RLEByteVecRef {
+            bytes: self.next_typed(FieldType::Rle, name)?,
+            canonical: self.canonical,
+        })
+    }
+}
+
+impl<'a> Iterator for RLEFieldIter<'a> {
+    type Item = Result<(FieldType, &'a [u8]), SerialiseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished() {
+            return None;
+        }
+
+        let field = decode_len(self.bytes, self.offset).and_then(|(field_type, len, consumed)| {
+            if self.canonical && consumed - 1 != len_bytes_needed(len) {
+                return Err(SerialiseError::new(
+                    "Length prefix is not minimal".to_string(),
+                ));
+            }
+            let start = self.offset + consumed;
+            let len = usize::try_from(len)
+                .map_err(|_| SerialiseError::new("Length too large".to_string()))?;
+            let end = start
+                .checked_add(len)
+                .ok_or_else(|| SerialiseError::new("Length too large".to_string()))?;
+            let slice = self
+                .bytes
+                .get(start..end)
+                .ok_or_else(|| SerialiseError::new("Unexpected end of input".to_string()))?;
+            Ok((field_type, slice, end))
+        });
+
+        match field {
+            Ok((field_type, slice, end)) => {
+                self.offset = end;
+                Some(Ok((field_type, slice)))
+            }
+            Err(e) => {
+                // stop after the first error rather than resyncing on garbage
+                self.offset = self.bytes.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialise::RLEByteVec;
+    use base_xx::ByteVec;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_borrowed_fields() {
+        let mut nested = RLEByteVec::default();
+        nested.add_u64(9);
+
+        let mut rle = RLEByteVec::default();
+        rle.add_data(Rc::new(ByteVec::new(vec![1, 2, 3].into())));
+        rle.add_u64(42);
+        rle.add_string("borrowed");
+        rle.add_rle(&nested)
+            .unwrap_or_else(|e| panic!("add nested: {e}"));
+        let encoded = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode: {e}"));
+
+        let view = RLEByteVecRef::new(encoded.get_bytes());
+        let mut fields = view.iter();
+        let raw = fields
+            .next_typed(FieldType::Bytes, "raw")
+            .unwrap_or_else(|e| panic!("raw: {e}"));
+        assert_eq!(raw, &[1, 2, 3]);
+        assert!(std::ptr::eq(
+            raw.as_ptr(),
+            encoded.get_bytes()[2..].as_ptr()
+        ));
+        assert_eq!(fields.peek_type(), Some(FieldType::U64));
+        assert_eq!(fields.next_u64("n").ok(), Some(42));
+        assert_eq!(fields.next_str("s").ok(), Some("borrowed"));
+        let mut inner = fields
+            .next_rle("nested")
+            .unwrap_or_else(|e| panic!("nested: {e}"))
+            .iter();
+        assert_eq!(inner.next_u64("inner").ok(), Some(9));
+        assert!(fields.is_finished());
+        assert_eq!(fields.peek_type(), None);
+
+        assert_eq!(
+            view.get_typed(2, FieldType::Utf8, "s").ok(),
+            Some(&b"borrowed"[..])
+        );
+        assert!(view.get_typed(4, FieldType::Bytes, "missing").is_err());
+    }
+
+    #[test]
+    fn test_truncated_stops_iteration() {
+        let bytes = [0b0000_0000, 5, 1, 2];
+        let mut fields = RLEByteVecRef::new(&bytes).iter();
+        assert!(matches!(fields.next(), Some(Err(_))));
+        assert!(fields.next().is_none());
+    }
+
+    #[test]
+    fn test_canonical_rejects_padded_length() {
+        // length 3 written with two length bytes
+        let padded = [0b0010_0000, 3, 0, 1, 2, 3];
+        assert!(RLEByteVecRef::new(&padded)
+            .iter()
+            .next_typed(FieldType::Bytes, "a")
+            .is_ok());
+
+        let err = RLEByteVecRef::canonical(&padded)
+            .iter()
+            .next_typed(FieldType::Bytes, "a")
+            .err()
+            .map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Length prefix is not minimal"));
+    }
+
+    #[test]
+    fn test_canonical_rejects_trailing_fields() {
+        let bytes = [0b0000_0000, 1, 7, 0b0000_0000, 1, 8];
+
+        let mut lenient = RLEByteVecRef::new(&bytes).iter();
+        let _ = lenient.next();
+        assert!(lenient.finish("payload").is_ok());
+
+        let mut strict = RLEByteVecRef::canonical(&bytes).iter();
+        let _ = strict.next();
+        assert!(strict.finish("payload").is_err());
+        let _ = strict.next();
+        assert!(strict.finish("payload").is_ok());
+    }
+}
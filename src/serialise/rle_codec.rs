@@ -0,0 +1,375 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use base_xx::{ByteVec, SerialiseError};
+use chrono::{DateTime, TimeZone, Utc};
+use simple_sign::{Signature, SigningAlgorithm};
+use slahasher::{Hash, HashAlgorithm};
+
+use crate::address::public_address::PublicAddress;
+use crate::serialise::{FieldType, RLEByteVec, RLEByteVecRef, RLEFieldIter};
+
+/// A struct that encodes to an `RLEByteVec`, one field per struct member
+///
+/// Usually implemented with `#[derive(RleEncode)]`.
+pub trait RleEncode {
+    /// Encode every member, in declaration order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a member cannot be encoded
+    fn to_rle(&self) -> Result<RLEByteVec, SerialiseError>;
+
+    /// Encode to bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a member cannot be encoded
+    fn to_byte_vec(&self) -> Result<ByteVec, SerialiseError> {
+        ByteVec::try_from(&self.to_rle()?)
+    }
+}
+
+/// A struct that decodes from an `RLEByteVec`, one field per struct member
+///
+/// Usually implemented with `#[derive(RleDecode)]`.
+pub trait RleDecode: Sized {
+    /// Decode every member, in declaration order, from the fields that follow
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field is missing, of the wrong type or malformed
+    fn from_fields(fields: &mut RLEFieldIter<'_>) -> Result<Self, SerialiseError>;
+
+    /// Decode every member, in declaration order
+    ///
+    /// Canonical views reject trailing fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field is missing, of the wrong type or malformed
+    fn from_rle(value: RLEByteVecRef<'_>) -> Result<Self, SerialiseError> {
+        let mut fields = value.iter();
+        let decoded = Self::from_fields(&mut fields)?;
+        fields.finish("payload")?;
+        Ok(decoded)
+    }
+
+    /// Decode from bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field is missing, of the wrong type or malformed
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SerialiseError> {
+        Self::from_rle(RLEByteVecRef::new(bytes))
+    }
+
+    /// Decode from bytes, accepting only the canonical encoding
+    ///
+    /// Rejects non-minimal length prefixes and trailing fields, and checks
+    /// that re-encoding the value gives back exactly `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are malformed or not canonical
+    fn from_bytes_canonical(bytes: &[u8]) -> Result<Self, SerialiseError>
+    where
+        Self: RleEncode,
+    {
+        let decoded = Self::from_rle(RLEByteVecRef::canonical(bytes))?;
+        if decoded.to_byte_vec()?.get_bytes() != bytes {
+            return Err(SerialiseError::new("Encoding is not canonical".to_string()));
+        }
+        Ok(decoded)
+    }
+}
+
+/// A value that can be written as a single RLE field
+pub trait RleEncodeField {
+    /// Append this value as one field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value cannot be encoded
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError>;
+}
+
+/// A value that can be read from a single RLE field
+pub trait RleDecodeField: Sized {
+    /// Read this value from the next field
+    ///
+    /// `name` is used in error messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field is missing, of the wrong type or malformed
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError>;
+}
+
+impl RleEncodeField for u64 {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_u64(*self);
+        Ok(())
+    }
+}
+
+impl RleDecodeField for u64 {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        fields.next_u64(name)
+    }
+}
+
+impl RleEncodeField for i64 {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_i64(*self);
+        Ok(())
+    }
+}
+
+impl RleDecodeField for i64 {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        fields.next_i64(name)
+    }
+}
+
+impl RleEncodeField for String {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_string(self);
+        Ok(())
+    }
+}
+
+impl RleDecodeField for String {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        fields.next_str(name).map(ToString::to_string)
+    }
+}
+
+impl RleEncodeField for ByteVec {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_data(Rc::new(self.clone()));
+        Ok(())
+    }
+}
+
+impl RleDecodeField for ByteVec {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let bytes = fields.next_typed(FieldType::Bytes, name)?;
+        Ok(Self::new(bytes.to_vec().into()))
+    }
+}
+
+/// Whole seconds, matching the transaction timestamp encoding
+impl RleEncodeField for DateTime<Utc> {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_i64(self.timestamp());
+        Ok(())
+    }
+}
+
+impl RleDecodeField for DateTime<Utc> {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let seconds = fields.next_i64(name)?;
+        Utc.timestamp_opt(seconds, 0)
+            .single()
+            .ok_or_else(|| SerialiseError::new(format!("Invalid {name}")))
+    }
+}
+
+impl RleEncodeField for PublicAddress {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_data(Rc::new(ByteVec::try_from(self)?));
+        Ok(())
+    }
+}
+
+impl RleDecodeField for PublicAddress {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        Self::try_from(fields.next_typed(FieldType::Bytes, name)?)
+    }
+}
+
+impl RleEncodeField for Hash {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_hash(self)
+    }
+}
+
+impl RleDecodeField for Hash {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let bytes = fields.next_typed(FieldType::Hash, name)?;
+        if bytes.is_empty() {
+            return Err(SerialiseError::new(format!("Field {name} is empty")));
+        }
+        let hash = Self::try_from(Arc::new(ByteVec::new(bytes.to_vec().into())))?;
+        if hash.get_bytes().get_bytes().len() != hash_len(hash.get_algorithm()) {
+            return Err(SerialiseError::new(format!(
+                "Field {name} has the wrong digest length"
+            )));
+        }
+        Ok(hash)
+    }
+}
+
+/// Digest size in bytes for each hash algorithm
+const fn hash_len(algorithm: HashAlgorithm) -> usize {
+    match algorithm {
+        HashAlgorithm::RIPEMD160 => 20,
+        HashAlgorithm::KECCAK256 | HashAlgorithm::SHA256 => 32,
+        HashAlgorithm::KECCAK384 => 48,
+        HashAlgorithm::KECCAK512 => 64,
+    }
+}
+
+impl RleEncodeField for Signature {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_signature(self)
+    }
+}
+
+impl RleDecodeField for Signature {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let bytes = fields.next_typed(FieldType::Signature, name)?;
+        if bytes.is_empty() {
+            return Err(SerialiseError::new(format!("Field {name} is empty")));
+        }
+        let signature = Self::try_from(Arc::new(ByteVec::new(bytes.to_vec().into())))?;
+        let expected = match signature.get_algorithm() {
+            SigningAlgorithm::ED25519 | SigningAlgorithm::ECDSA => Some(64),
+            SigningAlgorithm::RSA => None,
+        };
+        if expected.is_some_and(|len| signature.get_signature().get_bytes().len() != len) {
+            return Err(SerialiseError::new(format!(
+                "Field {name} has the wrong signature length"
+            )));
+        }
+        Ok(signature)
+    }
+}
+
+impl<T: RleEncodeField> RleEncodeField for Rc<T> {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        self.as_ref().encode_field(rle)
+    }
+}
+
+impl<T: RleDecodeField> RleDecodeField for Rc<T> {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        T::decode_field(fields, name).map(Self::new)
+    }
+}
+
+impl<T: RleEncodeField> RleEncodeField for Arc<T> {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        self.as_ref().encode_field(rle)
+    }
+}
+
+impl<T: RleDecodeField> RleDecodeField for Arc<T> {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        T::decode_field(fields, name).map(Self::new)
+    }
+}
+
+/// A nested RLE field holding one field per element
+impl<T: RleEncodeField> RleEncodeField for Vec<T> {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        let mut nested = RLEByteVec::default();
+        for item in self {
+            item.encode_field(&mut nested)?;
+        }
+        rle.add_rle(&nested)
+    }
+}
+
+impl<T: RleDecodeField> RleDecodeField for Vec<T> {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let mut items = fields.next_rle(name)?.iter();
+        let mut out = Self::new();
+        while !items.is_finished() {
+            out.push(T::decode_field(&mut items, name)?);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialise::{RleDecode, RleEncode};
+
+    #[derive(Debug, PartialEq, Eq, RleEncode, RleDecode)]
+    struct Post {
+        title: String,
+        score: i64,
+    }
+
+    #[derive(Debug, PartialEq, Eq, RleEncode, RleDecode)]
+    struct Thread {
+        id: u64,
+        opening: Post,
+        replies: Vec<Post>,
+        tags: Vec<String>,
+    }
+
+    fn sample() -> Thread {
+        Thread {
+            id: 3,
+            opening: Post {
+                title: "hello".to_string(),
+                score: 10,
+            },
+            replies: vec![
+                Post {
+                    title: "first".to_string(),
+                    score: -1,
+                },
+                Post {
+                    title: "second".to_string(),
+                    score: 2,
+                },
+            ],
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn test_derive_roundtrip() {
+        let thread = sample();
+        let bytes = thread
+            .to_byte_vec()
+            .unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded =
+            Thread::from_bytes(bytes.get_bytes()).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(thread, decoded);
+    }
+
+    #[test]
+    fn test_derive_field_layout() {
+        let rle = sample().to_rle().unwrap_or_else(|e| panic!("encode: {e}"));
+        assert_eq!(
+            rle.get_types(),
+            &vec![
+                FieldType::U64,
+                FieldType::Rle,
+                FieldType::Rle,
+                FieldType::Rle
+            ]
+        );
+        let replies = rle
+            .get_rle(2, "replies")
+            .unwrap_or_else(|e| panic!("replies: {e}"));
+        assert_eq!(replies.get_types(), &vec![FieldType::Rle, FieldType::Rle]);
+    }
+
+    #[test]
+    fn test_derive_reports_field_name() {
+        let mut rle = RLEByteVec::default();
+        rle.add_string("not an id");
+        let bytes = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode: {e}"));
+        let err = Thread::from_bytes(bytes.get_bytes())
+            .err()
+            .map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Field id must be u64, found utf8"));
+    }
+}
@@ -0,0 +1,384 @@
+use std::io::{ErrorKind, Read};
+use std::rc::Rc;
+
+use base_xx::{ByteVec, SerialiseError};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::serialise::rle_bytevec::{decode_prefix, len_from_le};
+use crate::serialise::{FieldType, RLEByteVec};
+
+/// Default maximum size of a single field, 1 MiB
+pub const DEFAULT_MAX_FIELD_SIZE: usize = 1024 * 1024;
+
+/// Default maximum number of fields in a payload
+pub const DEFAULT_MAX_FIELD_COUNT: usize = 1024;
+
+/// Default maximum size of all fields together, 16 MiB
+pub const DEFAULT_MAX_TOTAL_SIZE: usize = 16 * 1024 * 1024;
+
+/// Limits applied while decoding untrusted RLE data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_field_names)]
+pub struct RLELimits {
+    max_field_size: usize,
+    max_field_count: usize,
+    max_total_size: usize,
+}
+
+impl RLELimits {
+    /// Create new limits, allowing up to the default total size
+    #[must_use]
+    pub const fn new(max_field_size: usize, max_field_count: usize) -> Self {
+        Self {
+            max_field_size,
+            max_field_count,
+            max_total_size: DEFAULT_MAX_TOTAL_SIZE,
+        }
+    }
+
+    /// Set the largest accepted size of all fields together
+    #[must_use]
+    pub const fn with_max_total_size(mut self, max_total_size: usize) -> Self {
+        self.max_total_size = max_total_size;
+        self
+    }
+
+    /// Largest accepted field, in bytes
+    #[must_use]
+    pub const fn get_max_field_size(&self) -> usize {
+        self.max_field_size
+    }
+
+    /// Largest accepted number of fields
+    #[must_use]
+    pub const fn get_max_field_count(&self) -> usize {
+        self.max_field_count
+    }
+
+    /// Largest accepted size of all fields together, in bytes
+    #[must_use]
+    pub const fn get_max_total_size(&self) -> usize {
+        self.max_total_size
+    }
+
+    fn check_len(&self, len: u64) -> Result<usize, SerialiseError> {
+        let len = usize::try_from(len)
+            .map_err(|_| SerialiseError::new("Length too large".to_string()))?;
+        if len > self.max_field_size {
+            return Err(SerialiseError::new(format!(
+                "Field of {len} bytes exceeds limit of {} bytes",
+                self.max_field_size
+            )));
+        }
+        Ok(len)
+    }
+}
+
+impl Default for RLELimits {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_FIELD_SIZE, DEFAULT_MAX_FIELD_COUNT)
+    }
+}
+
+/// Incremental `RLEByteVec` decoder
+///
+/// Pulls one field at a time from a `std::io::Read` or `tokio::io::AsyncRead`,
+/// checking the length prefix against `RLELimits` before reading the field, so
+/// an untrusted peer cannot make us buffer more than the limits allow.
+pub struct RLEReader<R> {
+    reader: R,
+    limits: RLELimits,
+    field_count: usize,
+    total_size: usize,
+}
+
+impl<R> RLEReader<R> {
+    /// Create a reader with the default limits
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self::with_limits(reader, RLELimits::default())
+    }
+
+    /// Create a reader with the given limits
+    #[must_use]
+    pub const fn with_limits(reader: R, limits: RLELimits) -> Self {
+        Self {
+            reader,
+            limits,
+            field_count: 0,
+            total_size: 0,
+        }
+    }
+
+    /// Number of fields read so far
+    #[must_use]
+    pub const fn get_field_count(&self) -> usize {
+        self.field_count
+    }
+
+    /// Give back the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn start_field(&mut self) -> Result<(), SerialiseError> {
+        if self.field_count >= self.limits.max_field_count {
+            return Err(SerialiseError::new(format!(
+                "More than {} fields",
+                self.limits.max_field_count
+            )));
+        }
+        self.field_count += 1;
+        Ok(())
+    }
+
+    fn check_len(&mut self, len: u64) -> Result<usize, SerialiseError> {
+        let len = self.limits.check_len(len)?;
+        let total_size = self.total_size.saturating_add(len);
+        if total_size > self.limits.max_total_size {
+            return Err(SerialiseError::new(format!(
+                "Fields exceed total limit of {} bytes",
+                self.limits.max_total_size
+            )));
+        }
+        self.total_size = total_size;
+        Ok(len)
+    }
+}
+
+fn io_error(e: &std::io::Error) -> SerialiseError {
+    if e.kind() == ErrorKind::UnexpectedEof {
+        SerialiseError::new("Unexpected end of input".to_string())
+    } else {
+        SerialiseError::new(format!("Read failed: {e}"))
+    }
+}
+
+impl<R: Read> RLEReader<R> {
+    /// Read the next field
+    ///
+    /// Returns `Ok(None)` when the input ends cleanly on a field boundary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input ends mid-field, a limit is exceeded or the
+    /// underlying reader fails
+    pub fn next_field(&mut self) -> Result<Option<(FieldType, ByteVec)>, SerialiseError> {
+        let mut prefix = [0u8; 1];
+        loop {
+            match self.reader.read(&mut prefix) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(io_error(&e)),
+            }
+        }
+        self.start_field()?;
+
+        let (field_type, len_bytes) = decode_prefix(prefix[0])?;
+        let mut len_buf = [0u8; 8];
+        self.reader
+            .read_exact(&mut len_buf[..len_bytes])
+            .map_err(|e| io_error(&e))?;
+        let len = self.check_len(len_from_le(&len_buf[..len_bytes]))?;
+
+        let mut data = vec![0u8; len];
+        self.reader
+            .read_exact(&mut data)
+            .map_err(|e| io_error(&e))?;
+        Ok(Some((field_type, ByteVec::new(data.into()))))
+    }
+
+    /// Read every remaining field into an `RLEByteVec`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any field fails to decode
+    #[allow(clippy::doc_markdown)]
+    pub fn read_all(mut self) -> Result<RLEByteVec, SerialiseError> {
+        let mut out = RLEByteVec::default();
+        while let Some((field_type, data)) = self.next_field()? {
+            out.add_field(field_type, Rc::new(data));
+        }
+        Ok(out)
+    }
+}
+
+impl<R: Read> Iterator for RLEReader<R> {
+    type Item = Result<(FieldType, ByteVec), SerialiseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_field().transpose()
+    }
+}
+
+impl<R: AsyncRead + Unpin> RLEReader<R> {
+    /// Read the next field from an async reader
+    ///
+    /// Returns `Ok(None)` when the input ends cleanly on a field boundary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input ends mid-field, a limit is exceeded or the
+    /// underlying reader fails
+    pub async fn next_field_async(
+        &mut self,
+    ) -> Result<Option<(FieldType, ByteVec)>, SerialiseError> {
+        let mut prefix = [0u8; 1];
+        loop {
+            match self.reader.read(&mut prefix).await {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(io_error(&e)),
+            }
+        }
+        self.start_field()?;
+
+        let (field_type, len_bytes) = decode_prefix(prefix[0])?;
+        let mut len_buf = [0u8; 8];
+        self.reader
+            .read_exact(&mut len_buf[..len_bytes])
+            .await
+            .map_err(|e| io_error(&e))?;
+        let len = self.check_len(len_from_le(&len_buf[..len_bytes]))?;
+
+        let mut data = vec![0u8; len];
+        self.reader
+            .read_exact(&mut data)
+            .await
+            .map_err(|e| io_error(&e))?;
+        Ok(Some((field_type, ByteVec::new(data.into()))))
+    }
+
+    /// Read every remaining field from an async reader into an `RLEByteVec`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any field fails to decode
+    #[allow(clippy::doc_markdown)]
+    pub async fn read_all_async(mut self) -> Result<RLEByteVec, SerialiseError> {
+        // `RLEByteVec` holds `Rc`s, which would make this future `!Send` if held across an await
+        let mut fields = Vec::new();
+        while let Some(field) = self.next_field_async().await? {
+            fields.push(field);
+        }
+        let mut out = RLEByteVec::default();
+        for (field_type, data) in fields {
+            out.add_field(field_type, Rc::new(data));
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sample() -> Vec<u8> {
+        let mut rle = RLEByteVec::default();
+        rle.add_u64(7);
+        rle.add_string("streamed");
+        rle.add_data(Rc::new(ByteVec::new(vec![5; 300].into())));
+        ByteVec::try_from(&rle)
+            .unwrap_or_else(|e| panic!("encode: {e}"))
+            .get_bytes()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_read_fields() {
+        let mut reader = RLEReader::new(Cursor::new(sample()));
+
+        let fields: Vec<_> = reader
+            .by_ref()
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|e| panic!("read: {e}"));
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].0, FieldType::U64);
+        assert_eq!(fields[1].1.get_bytes(), b"streamed");
+        assert_eq!(fields[2].1.get_bytes(), vec![5u8; 300].as_slice());
+        assert_eq!(reader.get_field_count(), 3);
+    }
+
+    #[test]
+    fn test_read_all_matches_in_memory_decode() {
+        let bytes = sample();
+        let streamed = RLEReader::new(Cursor::new(bytes.clone()))
+            .read_all()
+            .unwrap_or_else(|e| panic!("stream: {e}"));
+        let buffered = RLEByteVec::try_from(ByteVec::new(bytes.into()))
+            .unwrap_or_else(|e| panic!("buffer: {e}"));
+
+        assert_eq!(streamed.get_types(), buffered.get_types());
+        assert_eq!(streamed.get_data(), buffered.get_data());
+    }
+
+    #[test]
+    fn test_field_size_limit() {
+        // 8 length bytes claiming u64::MAX bytes of data
+        let mut bytes = vec![0b1110_0000];
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        let result = RLEReader::new(Cursor::new(bytes)).next_field();
+        assert!(result.is_err());
+
+        let limits = RLELimits::new(100, 10);
+        let result = RLEReader::with_limits(Cursor::new(sample()), limits).read_all();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_field_count_limit() {
+        let limits = RLELimits::new(DEFAULT_MAX_FIELD_SIZE, 2);
+        let result = RLEReader::with_limits(Cursor::new(sample()), limits).read_all();
+        let err = result.err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("More than 2 fields"));
+    }
+
+    #[test]
+    fn test_total_size_limit() {
+        // each field fits on its own, but together they are 300 bytes of data
+        let limits = RLELimits::default().with_max_total_size(300);
+        let result = RLEReader::with_limits(Cursor::new(sample()), limits).read_all();
+        let err = result.err().map(|e| e.to_string());
+        assert_eq!(
+            err.as_deref(),
+            Some("Fields exceed total limit of 300 bytes")
+        );
+
+        let limits = RLELimits::default().with_max_total_size(8 + 8 + 300);
+        assert!(RLEReader::with_limits(Cursor::new(sample()), limits)
+            .read_all()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let mut bytes = sample();
+        bytes.pop();
+        let result = RLEReader::new(Cursor::new(bytes)).read_all();
+        let err = result.err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Unexpected end of input"));
+    }
+
+    #[tokio::test]
+    async fn test_async_read() {
+        let (mut tx, rx) = tokio::io::duplex(16);
+        let bytes = sample();
+        let writer = tokio::spawn(async move {
+            tokio::io::AsyncWriteExt::write_all(&mut tx, &bytes)
+                .await
+                .unwrap_or_else(|e| panic!("write: {e}"));
+        });
+
+        let rle = RLEReader::new(rx)
+            .read_all_async()
+            .await
+            .unwrap_or_else(|e| panic!("read: {e}"));
+        let _ = writer.await;
+
+        assert_eq!(rle.get_u64(0, "n").ok(), Some(7));
+        assert_eq!(rle.get_string(1, "s").ok().as_deref(), Some("streamed"));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use simple_sign::Ed25519Signer;
+
+use crate::address::public_address::PublicAddress;
+use crate::consensus::{Consensus, Slot};
+use crate::game::Block;
+use crate::transactions::SignedTransaction;
+
+/// Lowercase hex of `bytes`, for comparing against published test vectors
+#[must_use]
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+/// Bytes of lowercase or uppercase hex `text`
+#[must_use]
+pub fn unhex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap_or_else(|_| unreachable!()))
+        .collect()
+}
+
+/// Signer proposing empty blocks with valid tickets
+pub struct Proposer {
+    /// Key signing blocks and tickets
+    pub signer: Arc<Ed25519Signer>,
+    /// Address of `signer`
+    pub address: Rc<PublicAddress>,
+}
+
+impl Proposer {
+    /// Proposer with a fresh random key
+    #[must_use]
+    pub fn new() -> Self {
+        let signer = Arc::new(Ed25519Signer::new_random());
+        let address =
+            Rc::new(PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!()));
+        Self { signer, address }
+    }
+
+    /// Signed empty block at `time` building on `parent`, with its ticket
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block cannot be built or signed
+    #[must_use]
+    pub fn propose(&self, time: DateTime<Utc>, parent: &Block) -> Block {
+        self.propose_with(time, parent, Vec::new())
+    }
+
+    /// Signed block of `transactions` at `time` building on `parent`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block cannot be built or signed
+    #[must_use]
+    pub fn propose_with(
+        &self,
+        time: DateTime<Utc>,
+        parent: &Block,
+        transactions: Vec<SignedTransaction>,
+    ) -> Block {
+        let ticket = Consensus::draw_ticket(&self.signer, Slot::from_time(&time), parent)
+            .unwrap_or_else(|e| panic!("ticket: {e}"));
+        let mut block = Block::new(
+            time,
+            parent.hash().unwrap_or_else(|e| panic!("hash: {e}")),
+            Rc::clone(&self.address),
+            transactions,
+        )
+        .unwrap_or_else(|e| panic!("block: {e}"))
+        .with_ticket(ticket);
+        block
+            .sign(Arc::clone(&self.signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        block
+    }
+}
+
+impl Default for Proposer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,255 @@
+use std::num::NonZeroUsize;
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use ed25519_dalek::{Signature, VerifyingKey};
+
+use crate::address::KeyAlgorithm;
+use crate::transactions::SignedTransaction;
+
+/// Smallest chunk handed to a worker, below which threads cost more than they save
+const MIN_CHUNK: usize = 64;
+
+/// An Ed25519 signature pulled out of its transaction so it can cross threads
+struct Ed25519Item {
+    index: usize,
+    message: Vec<u8>,
+    key: VerifyingKey,
+    signature: Signature,
+}
+
+/// Pull out the parts needed to batch check an Ed25519 transaction
+///
+/// Returns `None` unless the transaction can be batched, leaving it to
+/// `SignedTransaction::verify`: other algorithms, mismatched ids, malformed
+/// keys or signatures, and keys or `R` points that are not canonical or have a
+/// small-order component. Batch verification treats those differently from
+/// `verify_strict`, so batching them could accept a block that checking one
+/// transaction at a time would refuse.
+fn ed25519_item(index: usize, signed: &SignedTransaction) -> Option<Ed25519Item> {
+    let from = signed.get_transaction().get_from();
+    if from.get_algorithm() != KeyAlgorithm::Ed25519 {
+        return None;
+    }
+    let transaction_signature = signed.get_signature();
+    if !transaction_signature.matches(signed.get_transaction())
+        || Some(transaction_signature.get_signature().get_algorithm())
+            != KeyAlgorithm::Ed25519.signing_algorithm()
+    {
+        return None;
+    }
+    let key = <[u8; 32]>::try_from(from.get_public_key().get_bytes()).ok()?;
+    let key = VerifyingKey::from_bytes(&key).ok()?;
+    let signature = Signature::from_slice(
+        transaction_signature
+            .get_signature()
+            .get_signature()
+            .get_bytes(),
+    )
+    .ok()?;
+    let r = CompressedEdwardsY(*signature.r_bytes());
+    let r_point = r.decompress().filter(|point| point.compress() == r)?;
+    let key_point = key.to_edwards();
+    // the identity is torsion free but small order, which verify_strict refuses
+    if [r_point, key_point]
+        .iter()
+        .any(|point| point.is_small_order() || !point.is_torsion_free())
+    {
+        return None;
+    }
+    Some(Ed25519Item {
+        index,
+        message: transaction_signature
+            .get_id()
+            .get_bytes()
+            .get_bytes()
+            .to_vec(),
+        key,
+        signature,
+    })
+}
+
+/// Indexes of the items in `chunk` that fail, checking one by one only if the
+/// batch as a whole fails
+fn verify_chunk(chunk: &[Ed25519Item]) -> Vec<usize> {
+    let messages: Vec<&[u8]> = chunk.iter().map(|item| item.message.as_slice()).collect();
+    let signatures: Vec<Signature> = chunk.iter().map(|item| item.signature).collect();
+    let keys: Vec<VerifyingKey> = chunk.iter().map(|item| item.key).collect();
+    if ed25519_dalek::verify_batch(&messages, &signatures, &keys).is_ok() {
+        return vec![];
+    }
+    chunk
+        .iter()
+        .filter(|item| {
+            item.key
+                .verify_strict(&item.message, &item.signature)
+                .is_err()
+        })
+        .map(|item| item.index)
+        .collect()
+}
+
+/// Verify many signed transactions, returning the indexes of those that fail
+///
+/// Ed25519 signatures are checked with batch verification split across a
+/// worker per available core. Other algorithms, and Ed25519 signatures that
+/// cannot be batched safely, fall back to `SignedTransaction::verify`, so the
+/// result always matches checking each transaction on its own. It is sorted
+/// and empty when every transaction is valid.
+#[must_use]
+pub fn verify_batch(transactions: &[SignedTransaction]) -> Vec<usize> {
+    let mut failed = Vec::new();
+    let mut items = Vec::new();
+    for (index, signed) in transactions.iter().enumerate() {
+        match ed25519_item(index, signed) {
+            Some(item) => items.push(item),
+            None if signed.verify() => {}
+            None => failed.push(index),
+        }
+    }
+
+    let workers = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = items.len().div_ceil(workers).max(MIN_CHUNK);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| (chunk, scope.spawn(|| verify_chunk(chunk))))
+            .collect();
+        for (chunk, handle) in handles {
+            // a panicking worker verified nothing, so count its chunk as failed
+            match handle.join() {
+                Ok(chunk_failed) => failed.extend(chunk_failed),
+                Err(_) => failed.extend(chunk.iter().map(|item| item.index)),
+            }
+        }
+    });
+
+    failed.sort_unstable();
+    failed.dedup();
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::public_address::PublicAddress;
+    use crate::transactions::{Transaction, TransactionSignature};
+    use chrono::Utc;
+    use simple_sign::{Ed25519Signer, Secp256k1Signer, Signer};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    fn signed_transactions(count: u64) -> Vec<SignedTransaction> {
+        let signer = Arc::new(Ed25519Signer::new_random());
+        let from =
+            Rc::new(PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!()));
+        let now = Utc::now();
+        (0..count)
+            .map(|nonce| {
+                let transaction =
+                    Transaction::new(Rc::clone(&from), Rc::clone(&from), 1, now, nonce);
+                SignedTransaction::new(transaction, Arc::clone(&signer))
+                    .unwrap_or_else(|e| panic!("sign: {e}"))
+            })
+            .collect()
+    }
+
+    fn secp256k1_transaction<S: Signer>(signer: &Secp256k1Signer, by: Arc<S>) -> SignedTransaction {
+        let from = Rc::new(PublicAddress::try_from(signer).unwrap_or_else(|_| unreachable!()));
+        let transaction = Transaction::new(Rc::clone(&from), from, 1, Utc::now(), 0);
+        SignedTransaction::new(transaction, by).unwrap_or_else(|e| panic!("sign: {e}"))
+    }
+
+    #[test]
+    fn test_all_valid() {
+        let mut transactions = signed_transactions(200);
+        let secp256k1 = Arc::new(Secp256k1Signer::new_random());
+        transactions.push(secp256k1_transaction(&secp256k1, Arc::clone(&secp256k1)));
+        assert!(verify_batch(&transactions).is_empty());
+        assert!(verify_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_reports_failures() {
+        let mut transactions = signed_transactions(150);
+
+        // signed by someone else
+        let stranger = Arc::new(Ed25519Signer::new_random());
+        let forged = SignedTransaction::new(
+            Transaction::new(
+                Rc::clone(transactions[10].get_transaction().get_from()),
+                Rc::clone(transactions[10].get_transaction().get_to()),
+                1_000,
+                Utc::now(),
+                10,
+            ),
+            stranger,
+        )
+        .unwrap_or_else(|e| panic!("sign: {e}"));
+        transactions[10] = forged;
+
+        // signature from another transaction
+        let borrowed = TransactionSignature::from_parts(
+            Arc::clone(transactions[99].get_signature().get_id()),
+            Arc::clone(transactions[99].get_signature().get_signature()),
+        );
+        let swapped = SignedTransaction::from_parts(
+            Transaction::new(
+                Rc::clone(transactions[120].get_transaction().get_from()),
+                Rc::clone(transactions[120].get_transaction().get_to()),
+                7,
+                Utc::now(),
+                120,
+            ),
+            borrowed,
+        );
+        transactions[120] = swapped;
+
+        let secp256k1 = Arc::new(Secp256k1Signer::new_random());
+        transactions.push(secp256k1_transaction(
+            &secp256k1,
+            Arc::new(Secp256k1Signer::new_random()),
+        ));
+
+        assert_eq!(verify_batch(&transactions), vec![10, 120, 150]);
+        let serial: Vec<usize> = transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, signed)| !signed.verify())
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(serial, vec![10, 120, 150]);
+    }
+
+    #[test]
+    fn test_small_order_key_matches_strict() {
+        use crate::address::public_address::PublicAddress;
+        use base_xx::ByteVec;
+        use simple_sign::{Signature, SigningAlgorithm};
+
+        // identity key with R = identity and s = 0 satisfies the batch
+        // equation for any message, but verify_strict refuses it
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let from = Rc::new(PublicAddress::new_with_algorithm(
+            KeyAlgorithm::Ed25519,
+            ByteVec::new(identity.to_vec().into()),
+        ));
+        let transaction = Transaction::new(Rc::clone(&from), from, 1_000, Utc::now(), 0);
+        let id = transaction.id().unwrap_or_else(|e| panic!("id: {e}"));
+        let mut bytes = identity.to_vec();
+        bytes.extend_from_slice(&[0; 32]);
+        let signature = Arc::new(Signature::new_with_algorithm(
+            SigningAlgorithm::ED25519,
+            Arc::new(ByteVec::new(bytes.into())),
+        ));
+        let forged = SignedTransaction::from_parts(
+            transaction,
+            TransactionSignature::from_parts(id, signature),
+        );
+        assert!(!forged.verify());
+
+        let mut transactions = signed_transactions(100);
+        transactions.insert(40, forged);
+        assert_eq!(verify_batch(&transactions), vec![40]);
+    }
+}
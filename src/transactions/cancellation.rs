@@ -0,0 +1,111 @@
+use base_xx::{byte_vec::Encodable, ByteVec, SerialiseError};
+use chrono::{DateTime, Utc};
+use simple_sign::{SignatureError, Signer};
+use slahasher::Hash;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::serialise::{RleDecode, RleEncode};
+use crate::transactions::{SignedTransaction, Transaction};
+
+/// The sender's withdrawal of a time-locked transaction before it matures
+///
+/// Carries a transfer of nothing from the sender to themself, with the locked
+/// transaction's nonce and no time lock. Once a block includes it the nonce is
+/// used up, so no node can ever apply the locked transaction.
+///
+/// Encodes as RLE fields `[id][transaction]`, where `id` is the id of the
+/// transaction being withdrawn.
+#[derive(Debug, PartialEq, Eq, RleEncode, RleDecode)]
+pub struct Cancellation {
+    /// Id of the transaction to cancel
+    id: Arc<Hash>,
+
+    /// Sender's transaction using up the nonce
+    transaction: SignedTransaction,
+}
+
+impl Cancellation {
+    /// Cancel `locked`, signed by its sender at `timestamp` with `fee`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a transaction cannot be hashed or signed
+    pub fn new<S: Signer>(
+        locked: &Transaction,
+        timestamp: DateTime<Utc>,
+        fee: u64,
+        signer: Arc<S>,
+    ) -> Result<Self, SignatureError> {
+        let id = locked
+            .id()
+            .map_err(|e| SignatureError::new(format!("Failed to hash transaction: {e}")))?;
+        let from = locked.get_from();
+        let transaction = Transaction::new(
+            Rc::clone(from),
+            Rc::clone(from),
+            0,
+            timestamp,
+            locked.get_nonce(),
+        )
+        .with_fee(fee);
+        Ok(Self {
+            id,
+            transaction: SignedTransaction::new(transaction, signer)?,
+        })
+    }
+
+    /// Get the id of the transaction to cancel
+    #[must_use]
+    pub const fn get_id(&self) -> &Arc<Hash> {
+        &self.id
+    }
+
+    /// Get the transaction using up the nonce
+    #[must_use]
+    pub const fn get_transaction(&self) -> &SignedTransaction {
+        &self.transaction
+    }
+
+    /// Take the transaction using up the nonce, to submit like any other
+    #[must_use]
+    pub fn into_transaction(self) -> SignedTransaction {
+        self.transaction
+    }
+
+    /// Check that this is signed by the sender of `locked`, uses its nonce and
+    /// is not itself time-locked
+    #[must_use]
+    pub fn cancels(&self, locked: &Transaction) -> bool {
+        let transaction = self.transaction.get_transaction();
+        locked.id().is_ok_and(|id| id == self.id)
+            && transaction.get_from() == locked.get_from()
+            && transaction.get_nonce() == locked.get_nonce()
+            && transaction.get_valid_after().is_none()
+            && self.transaction.verify()
+    }
+}
+
+impl TryFrom<&Cancellation> for ByteVec {
+    type Error = SerialiseError;
+
+    fn try_from(value: &Cancellation) -> Result<Self, Self::Error> {
+        value.to_byte_vec()
+    }
+}
+
+impl base_xx::byte_vec::TryIntoByteVec for Cancellation {
+    fn try_into_byte_vec(value: Arc<Self>) -> Result<Arc<ByteVec>, SerialiseError> {
+        Ok(Arc::new(ByteVec::try_from(value.as_ref())?))
+    }
+}
+
+impl TryFrom<ByteVec> for Cancellation {
+    type Error = SerialiseError;
+
+    fn try_from(value: ByteVec) -> Result<Self, Self::Error> {
+        Self::from_bytes_canonical(value.get_bytes())
+    }
+}
+
+impl Encodable for Cancellation {}
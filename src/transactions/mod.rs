@@ -1,8 +1,24 @@
+/// batch signature verification
+pub mod batch_verify;
+
+/// withdrawal of a pending time-locked transaction
+pub mod cancellation;
+
 /// transaction type
 pub mod transaction;
 
+/// transaction from a shared account
+pub mod multisig_transaction;
+
+/// signed transaction envelope
+pub mod signed_transaction;
+
 /// transaction signature type
 pub mod transaction_signature;
 
+pub use batch_verify::verify_batch;
+pub use cancellation::Cancellation;
+pub use multisig_transaction::MultisigTransaction;
+pub use signed_transaction::SignedTransaction;
 pub use transaction::Transaction;
 pub use transaction_signature::TransactionSignature;
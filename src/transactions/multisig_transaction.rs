@@ -0,0 +1,307 @@
+use base_xx::{byte_vec::Encodable, ByteVec, SerialiseError};
+use simple_sign::{SignatureError, Signer};
+use std::sync::Arc;
+
+use crate::address::MultisigPolicy;
+use crate::serialise::{RleDecode, RleEncode};
+use crate::transactions::{Transaction, TransactionSignature};
+
+/// A transaction from a shared account with its members' signatures
+///
+/// Encodes as RLE fields `[transaction][policy][signatures]`. The policy is
+/// carried so anyone can check it hashes to the `from` address.
+///
+/// Spending from a shared account is not supported yet: the mempool, blocks
+/// and `Ledger::apply` only accept `SignedTransaction`, so this type stops at
+/// collecting and checking signatures.
+#[derive(Debug, PartialEq, Eq, RleEncode, RleDecode)]
+pub struct MultisigTransaction {
+    transaction: Transaction,
+    policy: MultisigPolicy,
+    signatures: Vec<TransactionSignature>,
+}
+
+impl MultisigTransaction {
+    /// Start collecting signatures for `transaction`
+    #[must_use]
+    pub const fn new(transaction: Transaction, policy: MultisigPolicy) -> Self {
+        Self {
+            transaction,
+            policy,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Get the transaction
+    #[must_use]
+    pub const fn get_transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Get the policy of the sending account
+    #[must_use]
+    pub const fn get_policy(&self) -> &MultisigPolicy {
+        &self.policy
+    }
+
+    /// Get the signatures collected so far
+    #[must_use]
+    pub fn get_signatures(&self) -> &[TransactionSignature] {
+        &self.signatures
+    }
+
+    /// Add a member's signature
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction cannot be hashed or signed
+    pub fn sign<S: Signer>(&mut self, signer: Arc<S>) -> Result<(), SignatureError> {
+        self.signatures
+            .push(TransactionSignature::new(&self.transaction, signer)?);
+        Ok(())
+    }
+
+    /// Number of distinct members with a valid signature over this transaction
+    #[must_use]
+    pub fn count_signers(&self) -> usize {
+        self.policy
+            .get_members()
+            .iter()
+            .filter(|member| {
+                self.signatures
+                    .iter()
+                    .any(|signature| signature.verify(&self.transaction, member))
+            })
+            .count()
+    }
+
+    /// Check that the policy is the `from` account's and that at least the
+    /// threshold of distinct members have signed
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        let Ok(address) = self.policy.address() else {
+            return false;
+        };
+        **self.transaction.get_from() == address
+            && u64::try_from(self.count_signers())
+                .is_ok_and(|signers| signers >= self.policy.get_threshold())
+    }
+}
+
+impl TryFrom<&MultisigTransaction> for ByteVec {
+    type Error = SerialiseError;
+
+    fn try_from(value: &MultisigTransaction) -> Result<Self, Self::Error> {
+        value.to_byte_vec()
+    }
+}
+
+impl base_xx::byte_vec::TryIntoByteVec for MultisigTransaction {
+    fn try_into_byte_vec(value: Arc<Self>) -> Result<Arc<ByteVec>, SerialiseError> {
+        Ok(Arc::new(ByteVec::try_from(value.as_ref())?))
+    }
+}
+
+impl TryFrom<ByteVec> for MultisigTransaction {
+    type Error = SerialiseError;
+
+    fn try_from(value: ByteVec) -> Result<Self, Self::Error> {
+        Self::from_bytes_canonical(value.get_bytes())
+    }
+}
+
+impl Encodable for MultisigTransaction {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::public_address::PublicAddress;
+    use crate::address::AddressError;
+    use chrono::Utc;
+    use simple_sign::{Ed25519Signer, Secp256k1Signer};
+    use std::rc::Rc;
+
+    fn members() -> (Vec<Arc<Ed25519Signer>>, Vec<Rc<PublicAddress>>) {
+        let signers: Vec<_> = (0..3)
+            .map(|_| Arc::new(Ed25519Signer::new_random()))
+            .collect();
+        let addresses = signers
+            .iter()
+            .map(|signer| {
+                Rc::new(PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!()))
+            })
+            .collect();
+        (signers, addresses)
+    }
+
+    fn treasury_payment(policy: MultisigPolicy) -> MultisigTransaction {
+        let from = Rc::new(policy.address().unwrap_or_else(|e| panic!("address: {e}")));
+        let to = Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        );
+        MultisigTransaction::new(Transaction::new(from, to, 25, Utc::now(), 0), policy)
+    }
+
+    #[test]
+    fn test_policy_address() {
+        let (_, addresses) = members();
+        let policy =
+            MultisigPolicy::new(2, addresses.clone()).unwrap_or_else(|e| panic!("policy: {e}"));
+        let reversed = MultisigPolicy::new(2, addresses.iter().rev().cloned().collect())
+            .unwrap_or_else(|e| panic!("policy: {e}"));
+        let three =
+            MultisigPolicy::new(3, addresses.clone()).unwrap_or_else(|e| panic!("policy: {e}"));
+
+        let address = policy.address().unwrap_or_else(|e| panic!("address: {e}"));
+        assert_eq!(reversed.address().ok().as_ref(), Some(&address));
+        assert_ne!(three.address().ok().as_ref(), Some(&address));
+
+        // the address survives its text form
+        let parsed: PublicAddress = address
+            .to_string()
+            .parse()
+            .unwrap_or_else(|e| panic!("parse: {e}"));
+        assert_eq!(parsed, address);
+
+        assert_eq!(
+            MultisigPolicy::new(0, addresses.clone()).err(),
+            Some(AddressError::BadThreshold(0, 3))
+        );
+        assert_eq!(
+            MultisigPolicy::new(4, addresses.clone()).err(),
+            Some(AddressError::BadThreshold(4, 3))
+        );
+        let mut repeated = addresses.clone();
+        repeated.push(Rc::clone(&addresses[0]));
+        assert!(matches!(
+            MultisigPolicy::new(2, repeated),
+            Err(AddressError::BadMember(_))
+        ));
+        let nested = vec![Rc::clone(&addresses[0]), Rc::new(address)];
+        assert!(matches!(
+            MultisigPolicy::new(1, nested),
+            Err(AddressError::BadMember(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_enforces_policy_rules() {
+        use crate::serialise::{RLEByteVec, RleEncodeField};
+
+        let (_, mut addresses) = members();
+        addresses.sort();
+        let encode = |threshold: u64, members: &[Rc<PublicAddress>]| {
+            let mut rle = RLEByteVec::default();
+            rle.add_u64(threshold);
+            members
+                .to_vec()
+                .encode_field(&mut rle)
+                .unwrap_or_else(|e| panic!("encode: {e}"));
+            ByteVec::try_from(&rle)
+                .unwrap_or_else(|e| panic!("encode: {e}"))
+                .get_bytes()
+                .to_vec()
+        };
+        let decode = |bytes: &[u8]| MultisigPolicy::from_bytes_canonical(bytes);
+
+        assert!(decode(&encode(2, &addresses)).is_ok());
+        assert!(decode(&encode(0, &addresses)).is_err());
+        assert!(decode(&encode(4, &addresses)).is_err());
+
+        let repeated = [Rc::clone(&addresses[0]), Rc::clone(&addresses[0])];
+        assert!(decode(&encode(2, &repeated)).is_err());
+
+        let unsorted: Vec<_> = addresses.iter().rev().cloned().collect();
+        let err = decode(&encode(2, &unsorted)).err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Multisig members are not in order"));
+
+        let inner = MultisigPolicy::new(1, addresses.clone())
+            .unwrap_or_else(|e| panic!("policy: {e}"))
+            .address()
+            .unwrap_or_else(|e| panic!("address: {e}"));
+        let mut nested = vec![Rc::clone(&addresses[0]), Rc::new(inner)];
+        nested.sort();
+        assert!(decode(&encode(1, &nested)).is_err());
+    }
+
+    #[test]
+    fn test_threshold_of_distinct_members() {
+        let (signers, addresses) = members();
+        let policy = MultisigPolicy::new(2, addresses).unwrap_or_else(|e| panic!("policy: {e}"));
+        let mut payment = treasury_payment(policy);
+        assert!(!payment.verify());
+
+        payment
+            .sign(Arc::clone(&signers[0]))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        // the same member signing twice still counts once
+        payment
+            .sign(Arc::clone(&signers[0]))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        // outsiders do not count
+        payment
+            .sign(Arc::new(Secp256k1Signer::new_random()))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(payment.count_signers(), 1);
+        assert!(!payment.verify());
+
+        payment
+            .sign(Arc::clone(&signers[2]))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(payment.count_signers(), 2);
+        assert!(payment.verify());
+
+        let bytes = ByteVec::try_from(&payment).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded =
+            MultisigTransaction::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded, payment);
+        assert!(decoded.verify());
+    }
+
+    #[test]
+    fn test_policy_must_match_sender() {
+        let (signers, addresses) = members();
+        let policy =
+            MultisigPolicy::new(1, addresses.clone()).unwrap_or_else(|e| panic!("policy: {e}"));
+        // policies are not Clone; an identical one hashes to the same address
+        let same_policy =
+            MultisigPolicy::new(1, addresses).unwrap_or_else(|e| panic!("policy: {e}"));
+        let payment = treasury_payment(policy);
+
+        // same transaction, but claiming the account is an outsider's 1-of-1
+        let (outsiders, outsider_addresses) = members();
+        let single_policy = MultisigPolicy::new(1, vec![Rc::clone(&outsider_addresses[0])])
+            .unwrap_or_else(|e| panic!("policy: {e}"));
+        let mut forged = MultisigTransaction::new(
+            Transaction::new(
+                Rc::clone(payment.get_transaction().get_from()),
+                Rc::clone(payment.get_transaction().get_to()),
+                25,
+                *payment.get_transaction().get_timestamp(),
+                0,
+            ),
+            single_policy,
+        );
+        forged
+            .sign(Arc::clone(&outsiders[0]))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert_eq!(forged.count_signers(), 1);
+        assert!(!forged.verify());
+
+        let mut genuine = MultisigTransaction::new(
+            Transaction::new(
+                Rc::clone(payment.get_transaction().get_from()),
+                Rc::clone(payment.get_transaction().get_to()),
+                25,
+                *payment.get_transaction().get_timestamp(),
+                0,
+            ),
+            same_policy,
+        );
+        genuine
+            .sign(Arc::clone(&signers[1]))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert!(genuine.verify());
+    }
+}
@@ -0,0 +1,180 @@
+use base_xx::{byte_vec::Encodable, ByteVec, SerialiseError};
+use simple_sign::{Signature, SignatureError, Signer};
+use std::sync::Arc;
+
+use crate::serialise::{
+    RLEByteVec, RLEFieldIter, RleDecode, RleDecodeField, RleEncode, RleEncodeField,
+};
+use crate::transactions::{Transaction, TransactionSignature};
+
+/// A transaction together with its signature
+///
+/// Encodes as RLE fields `[transaction][signature]`. The id is not sent; it is
+/// recomputed from the transaction on decode, so a decoded signature always
+/// names the transaction it travelled with.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SignedTransaction {
+    transaction: Transaction,
+    signature: TransactionSignature,
+}
+
+impl SignedTransaction {
+    /// Sign `transaction`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction cannot be hashed or signed
+    pub fn new<S: Signer>(
+        transaction: Transaction,
+        signer: Arc<S>,
+    ) -> Result<Self, SignatureError> {
+        let signature = TransactionSignature::new(&transaction, signer)?;
+        Ok(Self {
+            transaction,
+            signature,
+        })
+    }
+
+    /// Bundle a transaction with an existing signature, unchecked until `verify`
+    #[must_use]
+    pub const fn from_parts(transaction: Transaction, signature: TransactionSignature) -> Self {
+        Self {
+            transaction,
+            signature,
+        }
+    }
+
+    /// Get the transaction
+    #[must_use]
+    pub const fn get_transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Get the signature
+    #[must_use]
+    pub const fn get_signature(&self) -> &TransactionSignature {
+        &self.signature
+    }
+
+    /// Check that the signature's id is this transaction's id and that it is
+    /// signed by the `from` address
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        self.signature
+            .verify(&self.transaction, self.transaction.get_from())
+    }
+}
+
+impl RleEncode for SignedTransaction {
+    fn to_rle(&self) -> Result<RLEByteVec, SerialiseError> {
+        let mut rle = RLEByteVec::default();
+        self.transaction.encode_field(&mut rle)?;
+        self.signature.get_signature().encode_field(&mut rle)?;
+        Ok(rle)
+    }
+}
+
+impl RleDecode for SignedTransaction {
+    fn from_fields(fields: &mut RLEFieldIter<'_>) -> Result<Self, SerialiseError> {
+        let transaction = Transaction::decode_field(fields, "transaction")?;
+        let signature = Arc::<Signature>::decode_field(fields, "signature")?;
+        let id = transaction.id()?;
+        Ok(Self::from_parts(
+            transaction,
+            TransactionSignature::from_parts(id, signature),
+        ))
+    }
+}
+
+impl RleEncodeField for SignedTransaction {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_rle(&self.to_rle()?)
+    }
+}
+
+impl RleDecodeField for SignedTransaction {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let mut nested = fields.next_rle(name)?.iter();
+        let value = Self::from_fields(&mut nested)?;
+        nested.finish(name)?;
+        Ok(value)
+    }
+}
+
+impl TryFrom<&SignedTransaction> for ByteVec {
+    type Error = SerialiseError;
+
+    fn try_from(value: &SignedTransaction) -> Result<Self, Self::Error> {
+        value.to_byte_vec()
+    }
+}
+
+impl base_xx::byte_vec::TryIntoByteVec for SignedTransaction {
+    fn try_into_byte_vec(value: Arc<Self>) -> Result<Arc<ByteVec>, SerialiseError> {
+        Ok(Arc::new(ByteVec::try_from(value.as_ref())?))
+    }
+}
+
+impl TryFrom<ByteVec> for SignedTransaction {
+    type Error = SerialiseError;
+
+    fn try_from(value: ByteVec) -> Result<Self, Self::Error> {
+        Self::from_bytes_canonical(value.get_bytes())
+    }
+}
+
+impl Encodable for SignedTransaction {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::public_address::PublicAddress;
+    use chrono::Utc;
+    use simple_sign::{Ed25519Signer, Secp256k1Signer};
+    use std::rc::Rc;
+
+    fn transaction(from: &Rc<PublicAddress>, amount: u64) -> Transaction {
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        Transaction::new(Rc::clone(from), Rc::new(to), amount, Utc::now(), 0)
+    }
+
+    #[test]
+    fn test_signed_roundtrip() {
+        let signer = Arc::new(Secp256k1Signer::new_random());
+        let from =
+            Rc::new(PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!()));
+        let original = SignedTransaction::new(transaction(&from, 12), signer)
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert!(original.verify());
+
+        let bytes = ByteVec::try_from(&original).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = SignedTransaction::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded, original);
+        assert!(decoded.verify());
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_signer_and_id() {
+        let signer = Arc::new(Ed25519Signer::new_random());
+        let from =
+            Rc::new(PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!()));
+
+        // signed by someone other than `from`
+        let forged =
+            SignedTransaction::new(transaction(&from, 5), Arc::new(Ed25519Signer::new_random()))
+                .unwrap_or_else(|e| panic!("sign: {e}"));
+        assert!(!forged.verify());
+
+        // signature taken from another transaction
+        let other = TransactionSignature::new(&transaction(&from, 6), Arc::clone(&signer))
+            .unwrap_or_else(|e| panic!("sign: {e}"));
+        let swapped = SignedTransaction::from_parts(transaction(&from, 5), other);
+        assert!(!swapped.verify());
+
+        // decoding recomputes the id, so the signature no longer matches it
+        let bytes = ByteVec::try_from(&swapped).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = SignedTransaction::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert!(!decoded.verify());
+    }
+}
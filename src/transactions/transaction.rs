@@ -1,39 +1,147 @@
 use base_xx::{byte_vec::Encodable, encoded_string::Decodable, ByteVec, SerialiseError};
 use chrono::{DateTime, TimeZone, Timelike, Utc};
-use slahasher::Hashable;
-
-use crate::{address::public_address::PublicAddress, serialise::RLEByteVec};
+use slahasher::{Hash, HashAlgorithm, Hashable};
+
+use crate::{
+    address::public_address::PublicAddress,
+    serialise::{
+        FieldType, RLEByteVec, RLEByteVecRef, RLEFieldIter, RleDecode, RleDecodeField, RleEncode,
+        RleEncodeField,
+    },
+};
 use std::rc::Rc;
 use std::sync::Arc;
 
+/// Encoding without a version field or nonce
+pub const TRANSACTION_VERSION_LEGACY: u64 = 1;
+
+/// Encoding written by `Transaction::new`
+pub const TRANSACTION_VERSION: u64 = 2;
+
+/// Longest accepted memo, in bytes
+pub const MAX_MEMO_LEN: usize = 256;
+
 /// A transaction between two public addresses.
-#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Version 2 encodes as RLE fields `[version][from][to][amount][timestamp][nonce]`,
+/// followed by `[fee]` when there is a fee or memo, `[memo]` when there is a
+/// memo and `[valid_after]` when the transaction is time-locked.
+/// Legacy version 1 bytes, `[from][to][amount][timestamp]` with the amount and
+/// timestamp as untagged 8-byte little-endian fields, still decode with a
+/// nonce of 0 and re-encode unchanged, so their ids stay the same.
+///
+/// Decoding from bytes only accepts the canonical encoding, since the
+/// transaction id is the hash of those bytes.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Transaction {
+    version: u64,
     from: Rc<PublicAddress>,
     to: Rc<PublicAddress>,
     amount: u64,
     /// Unix timestamp in seconds
     timestamp: DateTime<Utc>,
+    /// Number of earlier transactions sent by `from`
+    nonce: u64,
+    /// Paid by `from` to the signer of the block that includes the transaction
+    fee: u64,
+    /// Free text such as "tip for post X", at most `MAX_MEMO_LEN` bytes
+    memo: String,
+    /// Earliest block time at which the transaction can be applied
+    valid_after: Option<DateTime<Utc>>,
+}
+
+impl Default for Transaction {
+    fn default() -> Self {
+        Self {
+            version: TRANSACTION_VERSION,
+            from: Rc::default(),
+            to: Rc::default(),
+            amount: 0,
+            timestamp: DateTime::default(),
+            nonce: 0,
+            fee: 0,
+            memo: String::new(),
+            valid_after: None,
+        }
+    }
 }
 
 impl Transaction {
     /// Creates a new transaction.
+    ///
+    /// `nonce` must be the number of transactions `from` has sent before this
+    /// one, so that each transfer has a distinct id and cannot be replayed.
     #[must_use]
     pub fn new(
         from: Rc<PublicAddress>,
         to: Rc<PublicAddress>,
         amount: u64,
         timestamp: DateTime<Utc>,
+        nonce: u64,
     ) -> Self {
         let timestamp = timestamp.with_nanosecond(0).unwrap_or(timestamp);
         Self {
+            version: TRANSACTION_VERSION,
             from,
             to,
             amount,
             timestamp,
+            nonce,
+            fee: 0,
+            memo: String::new(),
+            valid_after: None,
         }
     }
 
+    /// Set the fee
+    ///
+    /// A legacy transaction cannot encode a fee, so it is upgraded to
+    /// `TRANSACTION_VERSION` and gets a new id.
+    #[must_use]
+    pub const fn with_fee(mut self, fee: u64) -> Self {
+        if fee != 0 {
+            self.version = TRANSACTION_VERSION;
+        }
+        self.fee = fee;
+        self
+    }
+
+    /// Set the memo
+    ///
+    /// A legacy transaction cannot encode a memo, so it is upgraded to
+    /// `TRANSACTION_VERSION` and gets a new id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memo is longer than `MAX_MEMO_LEN` bytes
+    pub fn with_memo(mut self, memo: impl Into<String>) -> Result<Self, SerialiseError> {
+        let memo = memo.into();
+        check_memo(&memo)?;
+        if !memo.is_empty() {
+            self.version = TRANSACTION_VERSION;
+        }
+        self.memo = memo;
+        Ok(self)
+    }
+
+    /// Hold the transaction until `valid_after`, for payments such as bounties
+    /// that only become due at a later date
+    ///
+    /// A legacy transaction cannot encode a time lock, so it is upgraded to
+    /// `TRANSACTION_VERSION` and gets a new id.
+    #[must_use]
+    pub fn with_valid_after(mut self, valid_after: DateTime<Utc>) -> Self {
+        self.version = TRANSACTION_VERSION;
+        self.valid_after = Some(valid_after.with_nanosecond(0).unwrap_or(valid_after));
+        self
+    }
+
+    /// Get encoding version
+    #[must_use]
+    pub const fn get_version(&self) -> u64 {
+        self.version
+    }
+
     /// Get from addtess
     #[must_use]
     pub const fn get_from(&self) -> &Rc<PublicAddress> {
@@ -57,29 +165,195 @@ impl Transaction {
     pub const fn get_timestamp(&self) -> &DateTime<Utc> {
         &self.timestamp
     }
-}
 
-impl TryFrom<&Transaction> for ByteVec {
-    type Error = SerialiseError;
+    /// Get nonce, always 0 for legacy transactions
+    #[must_use]
+    pub const fn get_nonce(&self) -> u64 {
+        self.nonce
+    }
 
-    fn try_from(value: &Transaction) -> Result<Self, Self::Error> {
-        let mut result = RLEByteVec::default();
+    /// Get fee
+    #[must_use]
+    pub const fn get_fee(&self) -> u64 {
+        self.fee
+    }
 
-        let from_rc = Rc::clone(&value.from);
-        let to_rc = Rc::clone(&value.to);
+    /// Get memo, empty if there is none
+    #[must_use]
+    pub fn get_memo(&self) -> &str {
+        &self.memo
+    }
 
-        let from_bytes = Self::try_from(&*from_rc)?;
-        let to_bytes = Self::try_from(&*to_rc)?;
+    /// Get the time lock, `None` if the transaction is valid at once
+    #[must_use]
+    pub const fn get_valid_after(&self) -> Option<&DateTime<Utc>> {
+        self.valid_after.as_ref()
+    }
 
-        result.add_data(Rc::new(from_bytes));
-        result.add_data(Rc::new(to_bytes));
-        result.add_data(Rc::new(Self::new(
-            value.amount.to_le_bytes().to_vec().into(),
-        )));
-        result.add_data(Rc::new(Self::new(
-            value.timestamp.timestamp().to_le_bytes().to_vec().into(),
+    /// Whether the time lock, if any, has passed at `now`
+    #[must_use]
+    pub fn is_mature(&self, now: &DateTime<Utc>) -> bool {
+        self.valid_after
+            .as_ref()
+            .is_none_or(|valid_after| valid_after <= now)
+    }
+
+    /// Amount plus fee, the total taken from `from`
+    #[must_use]
+    pub const fn get_total_cost(&self) -> Option<u64> {
+        self.amount.checked_add(self.fee)
+    }
+
+    /// Sum of the fees of `transactions`, collected by the block signer
+    ///
+    /// Returns `None` if the sum overflows.
+    pub fn total_fees<'a>(transactions: impl IntoIterator<Item = &'a Self>) -> Option<u64> {
+        transactions
+            .into_iter()
+            .try_fold(0u64, |total, transaction| {
+                total.checked_add(transaction.fee)
+            })
+    }
+
+    /// Transaction id, the KECCAK512 hash of the canonical encoding
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction cannot be encoded or hashed
+    pub fn id(&self) -> Result<Arc<Hash>, SerialiseError> {
+        Hash::try_hash(Arc::new(self.to_byte_vec()?), HashAlgorithm::KECCAK512)
+    }
+}
+
+/// Legacy 8-byte little-endian number, written as an untagged bytes field
+fn legacy_word(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<[u8; 8], SerialiseError> {
+    fields
+        .next_typed(FieldType::Bytes, name)?
+        .try_into()
+        .map_err(|_| SerialiseError::new(format!("Field {name} is not 8 bytes")))
+}
+
+fn legacy_field(rle: &mut RLEByteVec, word: [u8; 8]) {
+    rle.add_data(Rc::new(ByteVec::new(word.to_vec().into())));
+}
+
+fn check_memo(memo: &str) -> Result<(), SerialiseError> {
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(SerialiseError::new(format!(
+            "Memo of {} bytes exceeds limit of {MAX_MEMO_LEN} bytes",
+            memo.len()
         )));
-        Self::try_from(&result)
+    }
+    Ok(())
+}
+
+impl RleEncode for Transaction {
+    fn to_rle(&self) -> Result<RLEByteVec, SerialiseError> {
+        let mut rle = RLEByteVec::default();
+        if self.version != TRANSACTION_VERSION_LEGACY {
+            self.version.encode_field(&mut rle)?;
+        }
+        self.from.encode_field(&mut rle)?;
+        self.to.encode_field(&mut rle)?;
+        if self.version == TRANSACTION_VERSION_LEGACY {
+            legacy_field(&mut rle, self.amount.to_le_bytes());
+            legacy_field(&mut rle, self.timestamp.timestamp().to_le_bytes());
+        } else {
+            self.amount.encode_field(&mut rle)?;
+            self.timestamp.encode_field(&mut rle)?;
+            self.nonce.encode_field(&mut rle)?;
+            if self.fee != 0 || !self.memo.is_empty() {
+                self.fee.encode_field(&mut rle)?;
+            }
+            if !self.memo.is_empty() {
+                self.memo.encode_field(&mut rle)?;
+            }
+            if let Some(valid_after) = &self.valid_after {
+                valid_after.encode_field(&mut rle)?;
+            }
+        }
+        Ok(rle)
+    }
+}
+
+impl RleEncodeField for Transaction {
+    fn encode_field(&self, rle: &mut RLEByteVec) -> Result<(), SerialiseError> {
+        rle.add_rle(&self.to_rle()?)
+    }
+}
+
+impl RleDecode for Transaction {
+    fn from_fields(fields: &mut RLEFieldIter<'_>) -> Result<Self, SerialiseError> {
+        // legacy payloads start with the `from` address rather than a version
+        let version = if fields.peek_type() == Some(FieldType::U64) {
+            u64::decode_field(fields, "version")?
+        } else {
+            TRANSACTION_VERSION_LEGACY
+        };
+        if version != TRANSACTION_VERSION_LEGACY && version != TRANSACTION_VERSION {
+            return Err(SerialiseError::new(format!(
+                "Unknown transaction version {version}"
+            )));
+        }
+
+        let from = Rc::<PublicAddress>::decode_field(fields, "from")?;
+        let to = Rc::<PublicAddress>::decode_field(fields, "to")?;
+        let (amount, timestamp) = if version == TRANSACTION_VERSION_LEGACY {
+            let amount = u64::from_le_bytes(legacy_word(fields, "amount")?);
+            let seconds = i64::from_le_bytes(legacy_word(fields, "timestamp")?);
+            let timestamp = Utc
+                .timestamp_opt(seconds, 0)
+                .single()
+                .ok_or_else(|| SerialiseError::new("Invalid timestamp".to_string()))?;
+            (amount, timestamp)
+        } else {
+            (
+                u64::decode_field(fields, "amount")?,
+                DateTime::<Utc>::decode_field(fields, "timestamp")?,
+            )
+        };
+        let mut transaction = Self {
+            version,
+            from,
+            to,
+            amount,
+            timestamp,
+            ..Self::default()
+        };
+        if version == TRANSACTION_VERSION_LEGACY {
+            return Ok(transaction);
+        }
+
+        transaction.nonce = u64::decode_field(fields, "nonce")?;
+        if fields.peek_type() == Some(FieldType::U64) {
+            transaction.fee = u64::decode_field(fields, "fee")?;
+        }
+        if fields.peek_type() == Some(FieldType::Utf8) {
+            let memo = fields.next_str("memo")?;
+            check_memo(memo)?;
+            transaction.memo = memo.to_string();
+        }
+        if fields.peek_type() == Some(FieldType::I64) {
+            transaction.valid_after = Some(DateTime::<Utc>::decode_field(fields, "valid_after")?);
+        }
+        Ok(transaction)
+    }
+}
+
+impl RleDecodeField for Transaction {
+    fn decode_field(fields: &mut RLEFieldIter<'_>, name: &str) -> Result<Self, SerialiseError> {
+        let mut nested = fields.next_rle(name)?.iter();
+        let value = Self::from_fields(&mut nested)?;
+        nested.finish(name)?;
+        Ok(value)
+    }
+}
+
+impl TryFrom<&Transaction> for ByteVec {
+    type Error = SerialiseError;
+
+    fn try_from(value: &Transaction) -> Result<Self, Self::Error> {
+        value.to_byte_vec()
     }
 }
 
@@ -92,7 +366,7 @@ impl base_xx::byte_vec::TryIntoByteVec for Transaction {
 impl TryFrom<Arc<ByteVec>> for Transaction {
     type Error = SerialiseError;
     fn try_from(value: Arc<ByteVec>) -> Result<Self, Self::Error> {
-        Self::try_from((*value).clone())
+        Self::from_bytes_canonical(value.get_bytes())
     }
 }
 
@@ -100,63 +374,23 @@ impl TryFrom<ByteVec> for Transaction {
     type Error = SerialiseError;
 
     fn try_from(value: ByteVec) -> Result<Self, Self::Error> {
-        let rle = RLEByteVec::try_from(value)?;
-        let rle = rle.get_data();
-        let from_bytes = rle.first();
-        let to_bytes = rle.get(1);
-        let amount_bytes = rle.get(2);
-        let timestamp_bytes = rle.get(3);
-
-        let from_bytes = from_bytes
-            .ok_or_else(|| SerialiseError::new("Missing from field".to_string()))?
-            .as_ref()
-            .get_bytes();
-        let from = PublicAddress::try_from(ByteVec::new(from_bytes.to_vec().into()))?;
+        Self::from_bytes_canonical(value.get_bytes())
+    }
+}
 
-        let to_bytes = to_bytes
-            .ok_or_else(|| SerialiseError::new("Missing to field".to_string()))?
-            .as_ref()
-            .get_bytes();
-        let to = PublicAddress::try_from(ByteVec::new(to_bytes.to_vec().into()))?;
-        let amount_bytes = amount_bytes
-            .ok_or_else(|| SerialiseError::new("Missing amount field".to_string()))?
-            .as_ref()
-            .get_bytes();
-        if amount_bytes.len() != 8 {
-            return Err(SerialiseError::new(
-                "Amount field must be 8 bytes (u64 little-endian)".to_string(),
-            ));
-        }
-        let amount: u64 = u64::from_le_bytes(
-            amount_bytes
-                .try_into()
-                .map_err(|_| SerialiseError::new("Invalid amount bytes".to_string()))?,
-        );
-        let timestamp_bytes = timestamp_bytes
-            .ok_or_else(|| SerialiseError::new("Missing timestamp".to_string()))?
-            .as_ref()
-            .get_bytes();
-        if timestamp_bytes.len() != 8 {
-            return Err(SerialiseError::new(
-                "Timestamp field must be 8 bytes (i64 little-endian unix seconds)".to_string(),
-            ));
-        }
-        let timestamp_seconds: i64 = i64::from_le_bytes(
-            timestamp_bytes
-                .try_into()
-                .map_err(|_| SerialiseError::new("Invalid timestamp bytes".to_string()))?,
-        );
-        let timestamp = Utc
-            .timestamp_opt(timestamp_seconds, 0)
-            .single()
-            .ok_or_else(|| SerialiseError::new("Invalid timestamp".to_string()))?;
-
-        Ok(Self {
-            from: Rc::new(from),
-            to: Rc::new(to),
-            amount,
-            timestamp,
-        })
+impl TryFrom<RLEByteVecRef<'_>> for Transaction {
+    type Error = SerialiseError;
+
+    fn try_from(value: RLEByteVecRef<'_>) -> Result<Self, Self::Error> {
+        Self::from_rle(value)
+    }
+}
+
+impl TryFrom<&RLEByteVec> for Transaction {
+    type Error = SerialiseError;
+
+    fn try_from(rle: &RLEByteVec) -> Result<Self, Self::Error> {
+        Self::try_from(ByteVec::try_from(rle)?)
     }
 }
 
@@ -171,7 +405,6 @@ mod tests {
 
     use super::*;
     use simple_sign::Ed25519Signer;
-    use slahasher::Hash;
     use slogger::debug;
     use std::sync::Arc;
 
@@ -194,6 +427,7 @@ mod tests {
             Rc::new(public_address2),
             100,
             Utc::now(),
+            0,
         );
         debug!("transaction: {transaction:?}");
 
@@ -231,6 +465,7 @@ mod tests {
             Rc::new(public_address2),
             100,
             Utc::now(),
+            0,
         );
 
         let transaction_bytes = ByteVec::try_from(&transaction).unwrap_or_else(|e| {
@@ -245,4 +480,319 @@ mod tests {
         debug!("transaction_from_bytes: {transaction_from_bytes:#?}");
         assert_eq!(transaction, transaction_from_bytes);
     }
+
+    #[test]
+    fn test_transaction_rejects_wrong_field_type() {
+        let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+
+        let mut rle = RLEByteVec::default();
+        rle.add_u64(TRANSACTION_VERSION);
+        rle.add_data(Rc::new(
+            ByteVec::try_from(&from).unwrap_or_else(|e| panic!("encode from: {e}")),
+        ));
+        rle.add_data(Rc::new(
+            ByteVec::try_from(&to).unwrap_or_else(|e| panic!("encode to: {e}")),
+        ));
+        rle.add_i64(100);
+        rle.add_i64(Utc::now().timestamp());
+        let bytes = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode rle: {e}"));
+
+        let err = Transaction::try_from(bytes).err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Field amount must be u64, found i64"));
+    }
+
+    #[test]
+    fn test_transaction_from_stream() {
+        let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let transaction = Transaction::new(Rc::new(from), Rc::new(to), 5, Utc::now(), 0);
+        let bytes = ByteVec::try_from(&transaction).unwrap_or_else(|e| panic!("encode: {e}"));
+
+        let rle = crate::serialise::RLEReader::new(bytes.get_bytes())
+            .read_all()
+            .unwrap_or_else(|e| panic!("stream: {e}"));
+        let decoded = Transaction::try_from(&rle).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(transaction, decoded);
+    }
+
+    /// Fields exactly as the encoder before versioning wrote them
+    fn legacy_layout(from: &PublicAddress, to: &PublicAddress, amount: u64) -> RLEByteVec {
+        let mut rle = RLEByteVec::default();
+        rle.add_data(Rc::new(
+            ByteVec::try_from(from).unwrap_or_else(|e| panic!("encode from: {e}")),
+        ));
+        rle.add_data(Rc::new(
+            ByteVec::try_from(to).unwrap_or_else(|e| panic!("encode to: {e}")),
+        ));
+        rle.add_data(Rc::new(ByteVec::new(amount.to_le_bytes().to_vec().into())));
+        rle.add_data(Rc::new(ByteVec::new(
+            Utc::now().timestamp().to_le_bytes().to_vec().into(),
+        )));
+        rle
+    }
+
+    #[test]
+    fn test_encoding_matches_field_layout() {
+        let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let now = Utc::now();
+
+        let mut rle = RLEByteVec::default();
+        rle.add_u64(TRANSACTION_VERSION);
+        rle.add_data(Rc::new(
+            ByteVec::try_from(&from).unwrap_or_else(|e| panic!("encode from: {e}")),
+        ));
+        rle.add_data(Rc::new(
+            ByteVec::try_from(&to).unwrap_or_else(|e| panic!("encode to: {e}")),
+        ));
+        rle.add_u64(250);
+        rle.add_i64(now.timestamp());
+        rle.add_u64(7);
+        let expected = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode rle: {e}"));
+
+        let transaction = Transaction::new(Rc::new(from), Rc::new(to), 250, now, 7);
+        let bytes = ByteVec::try_from(&transaction).unwrap_or_else(|e| panic!("encode: {e}"));
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_legacy_bytes_still_decode() {
+        let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let legacy = ByteVec::try_from(&legacy_layout(&from, &to, 30))
+            .unwrap_or_else(|e| panic!("encode rle: {e}"));
+
+        let decoded =
+            Transaction::try_from(legacy.clone()).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded.get_version(), TRANSACTION_VERSION_LEGACY);
+        assert_eq!(decoded.get_nonce(), 0);
+        assert_eq!(decoded.get_amount(), 30);
+        let reencoded = ByteVec::try_from(&decoded).unwrap_or_else(|e| panic!("encode: {e}"));
+        assert_eq!(reencoded, legacy);
+
+        // the legacy encoder never tagged its numbers
+        let mut tagged = RLEByteVec::default();
+        for field in legacy_layout(&from, &to, 30).get_data().iter().take(2) {
+            tagged.add_data(Rc::clone(field));
+        }
+        tagged.add_u64(30);
+        tagged.add_i64(Utc::now().timestamp());
+        let bytes = ByteVec::try_from(&tagged).unwrap_or_else(|e| panic!("encode rle: {e}"));
+        assert!(Transaction::try_from(bytes).is_err());
+
+        // the same transfer at a new nonce no longer shares the id
+        let current = Transaction::new(
+            Rc::clone(decoded.get_from()),
+            Rc::clone(decoded.get_to()),
+            30,
+            *decoded.get_timestamp(),
+            0,
+        );
+        assert_ne!(current.id().ok(), decoded.id().ok());
+        let next = Transaction::new(
+            Rc::clone(decoded.get_from()),
+            Rc::clone(decoded.get_to()),
+            30,
+            *decoded.get_timestamp(),
+            1,
+        );
+        assert_ne!(current.id().ok(), next.id().ok());
+    }
+
+    #[test]
+    fn test_unknown_version_rejected() {
+        let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let mut rle = RLEByteVec::default();
+        rle.add_u64(TRANSACTION_VERSION + 1);
+        for field in legacy_layout(&from, &to, 1).get_data() {
+            rle.add_data(Rc::clone(field));
+        }
+        let bytes = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode rle: {e}"));
+        let err = Transaction::try_from(bytes).err().map(|e| e.to_string());
+        assert_eq!(err.as_deref(), Some("Unknown transaction version 3"));
+    }
+
+    #[test]
+    fn test_transaction_rejects_non_canonical_bytes() {
+        let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let transaction = Transaction::new(Rc::new(from), Rc::new(to), 9, Utc::now(), 0);
+        let mut rle = transaction
+            .to_rle()
+            .unwrap_or_else(|e| panic!("encode: {e}"));
+        let canonical = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode: {e}"));
+
+        let decoded =
+            Transaction::try_from(canonical.clone()).unwrap_or_else(|e| panic!("decode: {e}"));
+        let reencoded = ByteVec::try_from(&decoded).unwrap_or_else(|e| panic!("encode: {e}"));
+        assert_eq!(reencoded, canonical);
+
+        // the first i64 reads as a time lock, the second is left over
+        rle.add_i64(1);
+        rle.add_i64(1);
+        let trailing = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode: {e}"));
+        assert!(Transaction::try_from(trailing).is_err());
+
+        // amount field padded from one to two length bytes
+        let mut padded = canonical.get_bytes().to_vec();
+        let amount_at = (2 + 8) + 2 * (2 + 33);
+        assert_eq!(padded[amount_at], 0b0000_0001);
+        padded[amount_at] = 0b0010_0001;
+        padded.insert(amount_at + 2, 0);
+        assert!(Transaction::from_bytes(&padded).is_ok());
+        assert!(Transaction::try_from(ByteVec::new(padded.into())).is_err());
+    }
+
+    #[test]
+    fn test_fee_and_memo() {
+        let from = Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        );
+        let to = Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        );
+        let now = Utc::now();
+        let plain = Transaction::new(Rc::clone(&from), Rc::clone(&to), 10, now, 0);
+        let roundtrip = |transaction: &Transaction| {
+            let bytes = ByteVec::try_from(transaction).unwrap_or_else(|e| panic!("encode: {e}"));
+            Transaction::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"))
+        };
+
+        let with_fee = Transaction::new(Rc::clone(&from), Rc::clone(&to), 10, now, 1).with_fee(2);
+        assert_eq!(roundtrip(&with_fee), with_fee);
+        assert_eq!(with_fee.get_total_cost(), Some(12));
+
+        let tip = Transaction::new(Rc::clone(&from), Rc::clone(&to), 10, now, 2)
+            .with_memo("tip for post 42")
+            .unwrap_or_else(|e| panic!("memo: {e}"));
+        assert_eq!(roundtrip(&tip), tip);
+        assert_eq!(roundtrip(&tip).get_memo(), "tip for post 42");
+
+        assert_eq!(Transaction::total_fees([&plain, &with_fee, &tip]), Some(2));
+        let huge =
+            Transaction::new(Rc::clone(&from), Rc::clone(&to), 10, now, 3).with_fee(u64::MAX);
+        assert_eq!(Transaction::total_fees([&with_fee, &huge]), None);
+        assert_eq!(huge.get_total_cost(), None);
+
+        // an explicit zero fee is not the canonical form of "no fee"
+        let mut rle = plain.to_rle().unwrap_or_else(|e| panic!("encode: {e}"));
+        rle.add_u64(0);
+        let padded = ByteVec::try_from(&rle).unwrap_or_else(|e| panic!("encode: {e}"));
+        assert!(Transaction::try_from(padded).is_err());
+    }
+
+    #[test]
+    fn test_fee_memo_and_lock_upgrade_legacy() {
+        let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let legacy = ByteVec::try_from(&legacy_layout(&from, &to, 30))
+            .unwrap_or_else(|e| panic!("encode rle: {e}"));
+        let decode = || Transaction::try_from(legacy.clone()).unwrap_or_else(|e| panic!("{e}"));
+        let roundtrip = |transaction: &Transaction| {
+            let bytes = ByteVec::try_from(transaction).unwrap_or_else(|e| panic!("encode: {e}"));
+            Transaction::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"))
+        };
+
+        // the fee and memo must be in the signed bytes, never dropped
+        let with_fee = decode().with_fee(5);
+        assert_eq!(with_fee.get_version(), TRANSACTION_VERSION);
+        assert_eq!(roundtrip(&with_fee), with_fee);
+        assert_ne!(with_fee.id().ok(), decode().id().ok());
+
+        let with_memo = decode()
+            .with_memo("legacy tip")
+            .unwrap_or_else(|e| panic!("memo: {e}"));
+        assert_eq!(with_memo.get_version(), TRANSACTION_VERSION);
+        assert_eq!(roundtrip(&with_memo), with_memo);
+
+        let with_lock = decode().with_valid_after(Utc::now() + chrono::TimeDelta::days(1));
+        assert_eq!(with_lock.get_version(), TRANSACTION_VERSION);
+        assert_eq!(roundtrip(&with_lock), with_lock);
+
+        // nothing to encode, so the legacy id is kept
+        let unchanged = decode().with_fee(0);
+        assert_eq!(unchanged.get_version(), TRANSACTION_VERSION_LEGACY);
+        assert_eq!(unchanged.id().ok(), decode().id().ok());
+    }
+
+    #[test]
+    fn test_memo_size_limit() {
+        let from = Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        );
+        let transaction = Transaction::new(Rc::clone(&from), from, 1, Utc::now(), 0);
+        let limit = "x".repeat(MAX_MEMO_LEN);
+        let transaction = transaction
+            .with_memo(limit)
+            .unwrap_or_else(|e| panic!("memo: {e}"));
+        let rle = transaction
+            .to_rle()
+            .unwrap_or_else(|e| panic!("encode: {e}"));
+
+        let too_long = "x".repeat(MAX_MEMO_LEN + 1);
+        assert!(Transaction::default().with_memo(too_long.clone()).is_err());
+
+        // replace the memo field with an oversized one
+        let mut oversized = RLEByteVec::default();
+        for (index, field) in rle.get_data().iter().enumerate().take(7) {
+            let field_type = rle.get_field_type(index).unwrap_or_else(|| unreachable!());
+            oversized.add_field(field_type, Rc::clone(field));
+        }
+        oversized.add_string(&too_long);
+        let bytes = ByteVec::try_from(&oversized).unwrap_or_else(|e| panic!("encode: {e}"));
+        let err = Transaction::try_from(bytes).err().map(|e| e.to_string());
+        assert_eq!(
+            err.as_deref(),
+            Some("Memo of 257 bytes exceeds limit of 256 bytes")
+        );
+    }
+
+    #[test]
+    fn test_valid_after() {
+        let from = Rc::new(
+            PublicAddress::try_from(&Ed25519Signer::new_random())
+                .unwrap_or_else(|_| unreachable!()),
+        );
+        let now = Utc::now();
+        let due = now + chrono::TimeDelta::days(30);
+        let plain = Transaction::new(Rc::clone(&from), Rc::clone(&from), 1, now, 0);
+        assert!(plain.is_mature(&now));
+
+        let bounty = Transaction::new(Rc::clone(&from), Rc::clone(&from), 1, now, 0)
+            .with_memo("bounty for post 7")
+            .unwrap_or_else(|e| panic!("memo: {e}"))
+            .with_valid_after(due);
+        assert!(!bounty.is_mature(&now));
+        assert!(bounty.is_mature(&due));
+        assert_ne!(bounty.id().ok(), plain.id().ok());
+
+        let bytes = ByteVec::try_from(&bounty).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = Transaction::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded, bounty);
+
+        // the lock needs neither fee nor memo before it
+        let locked = Transaction::new(Rc::clone(&from), from, 1, now, 0).with_valid_after(due);
+        let bytes = ByteVec::try_from(&locked).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded = Transaction::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded.get_valid_after(), locked.get_valid_after());
+    }
 }
@@ -1,12 +1,16 @@
 use base_xx::{byte_vec::Encodable, ByteVec, SerialiseError};
-use simple_sign::{Ed25519Signer, Signature, SignatureError, Signer};
-use slahasher::{Hash, HashAlgorithm};
+use simple_sign::{Signature, SignatureError, Signer};
+use slahasher::Hash;
 use std::sync::Arc;
 
+use crate::address::public_address::PublicAddress;
+use crate::serialise::{RleDecode, RleEncode};
 use crate::transactions::Transaction;
 
 /// Transaction signature
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Encodes as RLE fields `[id][signature]`.
+#[derive(Debug, PartialEq, Eq, RleEncode, RleDecode)]
 pub struct TransactionSignature {
     /// Hash/Id of the transaction
     id: Arc<Hash>,
@@ -27,14 +31,12 @@ impl TransactionSignature {
     ///
     /// # Errors
     /// * `SignatureError` - If the transaction cannot be hashed
-    pub fn new(
+    pub fn new<S: Signer>(
         transaction: &Transaction,
-        signer: Arc<Ed25519Signer>,
+        signer: Arc<S>,
     ) -> Result<Self, SignatureError> {
-        let bytes = base_xx::ByteVec::try_from(transaction)
-            .map_err(|e| SignatureError::new(format!("Failed to serialize transaction: {e}")))?;
-
-        let id = Hash::try_hash(Arc::new(bytes), HashAlgorithm::KECCAK512)
+        let id = transaction
+            .id()
             .map_err(|e| SignatureError::new(format!("Failed to hash transaction: {e}")))?;
 
         let signature = signer
@@ -43,22 +45,55 @@ impl TransactionSignature {
 
         Ok(Self { id, signature })
     }
+
+    /// Create a transaction signature from an id and a signature over it
+    #[must_use]
+    pub const fn from_parts(id: Arc<Hash>, signature: Arc<Signature>) -> Self {
+        Self { id, signature }
+    }
+
+    /// Get the transaction id the signature covers
+    #[must_use]
+    pub const fn get_id(&self) -> &Arc<Hash> {
+        &self.id
+    }
+
+    /// Get the signature
+    #[must_use]
+    pub const fn get_signature(&self) -> &Arc<Signature> {
+        &self.signature
+    }
+
+    /// Check that the id is the id of `transaction`
+    #[must_use]
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        transaction.id().is_ok_and(|id| id == self.id)
+    }
+
+    /// Check that this is `address`'s signature over `transaction`
+    ///
+    /// Both the id must be the id of `transaction` and the signature over it
+    /// must be `address`'s, so a signature lifted from another transaction is
+    /// refused.
+    #[must_use]
+    pub fn verify(&self, transaction: &Transaction, address: &PublicAddress) -> bool {
+        self.matches(transaction) && address.verify(&self.id, &self.signature)
+    }
 }
 
 impl TryFrom<&TransactionSignature> for ByteVec {
     type Error = SerialiseError;
 
     fn try_from(value: &TransactionSignature) -> Result<Self, Self::Error> {
-        let id_bytes =
-            <Hash as base_xx::byte_vec::TryIntoByteVec>::try_into_byte_vec(Arc::clone(&value.id))?;
-        let signature_bytes = <Signature as base_xx::byte_vec::TryIntoByteVec>::try_into_byte_vec(
-            Arc::clone(&value.signature),
-        )?;
-
-        let mut bytes = Vec::new();
-        bytes.extend_from_slice(id_bytes.get_bytes());
-        bytes.extend_from_slice(signature_bytes.get_bytes());
-        Ok(Self::new(bytes.into()))
+        value.to_byte_vec()
+    }
+}
+
+impl TryFrom<ByteVec> for TransactionSignature {
+    type Error = SerialiseError;
+
+    fn try_from(value: ByteVec) -> Result<Self, Self::Error> {
+        Self::from_bytes_canonical(value.get_bytes())
     }
 }
 
@@ -69,3 +104,119 @@ impl base_xx::byte_vec::TryIntoByteVec for TransactionSignature {
 }
 
 impl Encodable for TransactionSignature {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use simple_sign::{Ed25519Signer, Secp256k1Signer};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_sign_with_each_algorithm() {
+        let ed25519 = Arc::new(Ed25519Signer::new_random());
+        let secp256k1 = Arc::new(Secp256k1Signer::new_random());
+        let ed25519_address =
+            PublicAddress::try_from(ed25519.as_ref()).unwrap_or_else(|_| unreachable!());
+        let secp256k1_address =
+            PublicAddress::try_from(secp256k1.as_ref()).unwrap_or_else(|_| unreachable!());
+
+        let transaction = Transaction::new(
+            Rc::new(secp256k1_address),
+            Rc::new(ed25519_address),
+            10,
+            Utc::now(),
+            0,
+        );
+
+        let signature = TransactionSignature::new(&transaction, secp256k1)
+            .unwrap_or_else(|e| unreachable!("Error {e}"));
+        assert!(transaction
+            .get_from()
+            .verify(signature.get_id(), signature.get_signature()));
+        assert!(!transaction
+            .get_to()
+            .verify(signature.get_id(), signature.get_signature()));
+
+        let signature = TransactionSignature::new(&transaction, ed25519)
+            .unwrap_or_else(|e| unreachable!("Error {e}"));
+        assert!(transaction
+            .get_to()
+            .verify(signature.get_id(), signature.get_signature()));
+    }
+
+    fn signed_sample() -> (Transaction, TransactionSignature) {
+        let signer = Arc::new(Ed25519Signer::new_random());
+        let from = PublicAddress::try_from(signer.as_ref()).unwrap_or_else(|_| unreachable!());
+        let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+            .unwrap_or_else(|_| unreachable!());
+        let transaction = Transaction::new(Rc::new(from), Rc::new(to), 42, Utc::now(), 0);
+        let signature = TransactionSignature::new(&transaction, signer)
+            .unwrap_or_else(|e| unreachable!("Error {e}"));
+        (transaction, signature)
+    }
+
+    #[test]
+    fn test_signature_roundtrip() {
+        let (transaction, signature) = signed_sample();
+        let bytes = ByteVec::try_from(&signature).unwrap_or_else(|e| panic!("encode: {e}"));
+        let decoded =
+            TransactionSignature::try_from(bytes).unwrap_or_else(|e| panic!("decode: {e}"));
+        assert_eq!(decoded, signature);
+        assert!(decoded.matches(&transaction));
+        assert!(decoded.verify(&transaction, transaction.get_from()));
+        assert!(!decoded.verify(&transaction, transaction.get_to()));
+    }
+
+    #[test]
+    fn test_tampered_id() {
+        let (transaction, signature) = signed_sample();
+        let mut bytes = ByteVec::try_from(&signature)
+            .unwrap_or_else(|e| panic!("encode: {e}"))
+            .get_bytes()
+            .to_vec();
+        // last digest byte: prefix, length, algorithm, then 64 digest bytes
+        bytes[2 + 64] ^= 1;
+        let tampered = TransactionSignature::try_from(ByteVec::new(bytes.into()))
+            .unwrap_or_else(|e| panic!("decode: {e}"));
+        assert!(!tampered.matches(&transaction));
+        assert!(!tampered.verify(&transaction, transaction.get_from()));
+    }
+
+    #[test]
+    fn test_tampered_transaction() {
+        let (transaction, signature) = signed_sample();
+        let from = transaction.get_from();
+        assert!(signature.verify(&transaction, from));
+
+        // a genuine signature does not carry over to a changed transaction
+        let tampered = Transaction::new(
+            Rc::clone(from),
+            Rc::clone(transaction.get_to()),
+            transaction.get_amount() + 1,
+            *transaction.get_timestamp(),
+            transaction.get_nonce(),
+        );
+        assert!(!signature.verify(&tampered, from));
+    }
+
+    #[test]
+    fn test_tampered_signature() {
+        let (transaction, signature) = signed_sample();
+        let mut bytes = ByteVec::try_from(&signature)
+            .unwrap_or_else(|e| panic!("encode: {e}"))
+            .get_bytes()
+            .to_vec();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let tampered = TransactionSignature::try_from(ByteVec::new(bytes.into()))
+            .unwrap_or_else(|e| panic!("decode: {e}"));
+        assert!(tampered.matches(&transaction));
+        assert!(!tampered.verify(&transaction, transaction.get_from()));
+
+        // truncated payloads are rejected rather than misparsed
+        let bytes = ByteVec::try_from(&signature).unwrap_or_else(|e| panic!("encode: {e}"));
+        let truncated = bytes.get_bytes()[..bytes.get_bytes().len() - 1].to_vec();
+        assert!(TransactionSignature::try_from(ByteVec::new(truncated.into())).is_err());
+    }
+}
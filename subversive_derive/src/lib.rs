@@ -0,0 +1,103 @@
+#![deny(missing_docs)]
+
+//! Derive macros for encoding structs as `subversive::serialise::RLEByteVec`.
+//!
+//! Each named field becomes one RLE field, in declaration order, using the
+//! field type's `RleEncodeField`/`RleDecodeField` implementation.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, FieldsNamed};
+
+fn named_fields(input: &DeriveInput) -> Result<&FieldsNamed, syn::Error> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(fields),
+            _ => Err(syn::Error::new_spanned(
+                &input.ident,
+                "RLE derives need a struct with named fields",
+            )),
+        },
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            "RLE derives can only be used on structs",
+        )),
+    }
+}
+
+/// Derive `RleEncode` and `RleEncodeField` for a struct with named fields
+#[proc_macro_derive(RleEncode)]
+pub fn derive_rle_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match named_fields(&input) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let encode = fields.named.iter().map(|field| {
+        let ident = &field.ident;
+        quote! {
+            ::subversive::serialise::RleEncodeField::encode_field(&self.#ident, &mut rle)?;
+        }
+    });
+
+    quote! {
+        impl #impl_generics ::subversive::serialise::RleEncode for #name #ty_generics #where_clause {
+            fn to_rle(&self) -> ::std::result::Result<::subversive::serialise::RLEByteVec, ::subversive::serialise::SerialiseError> {
+                let mut rle = ::subversive::serialise::RLEByteVec::default();
+                #(#encode)*
+                Ok(rle)
+            }
+        }
+
+        impl #impl_generics ::subversive::serialise::RleEncodeField for #name #ty_generics #where_clause {
+            fn encode_field(&self, rle: &mut ::subversive::serialise::RLEByteVec) -> ::std::result::Result<(), ::subversive::serialise::SerialiseError> {
+                rle.add_rle(&::subversive::serialise::RleEncode::to_rle(self)?)
+            }
+        }
+    }
+    .into()
+}
+
+/// Derive `RleDecode` and `RleDecodeField` for a struct with named fields
+#[proc_macro_derive(RleDecode)]
+pub fn derive_rle_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match named_fields(&input) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let decode = fields.named.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let label = ident.as_ref().map(ToString::to_string).unwrap_or_default();
+        quote! {
+            #ident: <#ty as ::subversive::serialise::RleDecodeField>::decode_field(fields, #label)?,
+        }
+    });
+
+    quote! {
+        impl #impl_generics ::subversive::serialise::RleDecode for #name #ty_generics #where_clause {
+            fn from_fields(fields: &mut ::subversive::serialise::RLEFieldIter<'_>) -> ::std::result::Result<Self, ::subversive::serialise::SerialiseError> {
+                Ok(Self {
+                    #(#decode)*
+                })
+            }
+        }
+
+        impl #impl_generics ::subversive::serialise::RleDecodeField for #name #ty_generics #where_clause {
+            fn decode_field(fields: &mut ::subversive::serialise::RLEFieldIter<'_>, name: &str) -> ::std::result::Result<Self, ::subversive::serialise::SerialiseError> {
+                let mut nested = fields.next_rle(name)?.iter();
+                let value = <Self as ::subversive::serialise::RleDecode>::from_fields(&mut nested)?;
+                nested.finish(name)?;
+                Ok(value)
+            }
+        }
+    }
+    .into()
+}
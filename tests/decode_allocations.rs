@@ -0,0 +1,107 @@
+//! Allocation benchmark for `RLEByteVecRef` decoding
+//!
+//! Lives in its own test binary because it installs a counting global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use base_xx::ByteVec;
+use chrono::{TimeZone, Utc};
+use simple_sign::Ed25519Signer;
+
+use subversive::address::public_address::PublicAddress;
+use subversive::serialise::{FieldType, RLEByteVec, RLEByteVecRef};
+use subversive::transactions::transaction::TRANSACTION_VERSION;
+use subversive::transactions::Transaction;
+
+/// Counts allocations made on the current thread
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// The decode path before `RLEByteVecRef`: copy every field into an
+/// `RLEByteVec`, then copy the addresses again while reading fields by index
+fn decode_copying(bytes: ByteVec) -> Transaction {
+    let rle = RLEByteVec::try_from(bytes).unwrap_or_else(|e| unreachable!("broke {e}"));
+    let address = |index, name| {
+        let data = rle
+            .get_typed(index, FieldType::Bytes, name)
+            .unwrap_or_else(|e| unreachable!("broke {e}"));
+        PublicAddress::try_from(ByteVec::new(data.to_vec().into()))
+            .unwrap_or_else(|e| unreachable!("broke {e}"))
+    };
+    let number = |index, name| {
+        rle.get_u64(index, name)
+            .unwrap_or_else(|e| unreachable!("broke {e}"))
+    };
+    assert_eq!(number(0, "version"), TRANSACTION_VERSION);
+    let from = address(1, "from");
+    let to = address(2, "to");
+    let amount = number(3, "amount");
+    let seconds = rle
+        .get_i64(4, "timestamp")
+        .unwrap_or_else(|e| unreachable!("broke {e}"));
+    let timestamp = Utc
+        .timestamp_opt(seconds, 0)
+        .single()
+        .unwrap_or_else(|| unreachable!());
+    let nonce = number(5, "nonce");
+    Transaction::new(Rc::new(from), Rc::new(to), amount, timestamp, nonce)
+}
+
+#[test]
+fn test_decode_allocations() {
+    let from = PublicAddress::try_from(&Ed25519Signer::new_random())
+        .unwrap_or_else(|e| unreachable!("broke {e}"));
+    let to = PublicAddress::try_from(&Ed25519Signer::new_random())
+        .unwrap_or_else(|e| unreachable!("broke {e}"));
+    let transaction = Transaction::new(Rc::new(from), Rc::new(to), 100, Utc::now(), 3);
+    let bytes = ByteVec::try_from(&transaction).unwrap_or_else(|e| unreachable!("broke {e}"));
+
+    // both paths decode the same transaction
+    assert_eq!(decode_copying(bytes.clone()), transaction);
+    let borrowed = Transaction::try_from(RLEByteVecRef::new(bytes.get_bytes()))
+        .unwrap_or_else(|e| unreachable!("broke {e}"));
+    assert_eq!(borrowed, transaction);
+
+    let rounds = 1_000;
+
+    let before = allocations();
+    for _ in 0..rounds {
+        let _ = decode_copying(bytes.clone());
+    }
+    let copying = allocations() - before;
+
+    let before = allocations();
+    for _ in 0..rounds {
+        let _ = Transaction::try_from(RLEByteVecRef::new(bytes.get_bytes()))
+            .unwrap_or_else(|e| unreachable!("broke {e}"));
+    }
+    let borrowed = allocations() - before;
+
+    assert!(
+        borrowed < copying,
+        "borrowed decode made {borrowed} allocations, copying made {copying}"
+    );
+}